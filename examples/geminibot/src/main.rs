@@ -42,16 +42,13 @@ async fn ask_gemini(text: String) -> Result<Response, reqwest::Error> {
             parts: vec![Part { text }],
         }],
     };
-    let response = reqwest::Client::new()
+    reqwest::Client::new()
         .post(api_url)
         .json(&body)
         .send()
-        .await?;
-
-    match response.json().await {
-        Ok(response) => Ok(response),
-        Err(err) => panic!("{err:?}"),
-    }
+        .await?
+        .json()
+        .await
 }
 
 #[action]
@@ -60,7 +57,7 @@ async fn Main(res: Res, req: Req) {
     res.send(PersistentMenuModel::new(
         &req.user,
         vec![Button::Postback {
-            title: "AskGemini",
+            title: "AskGemini".to_owned(),
             payload: Payload::new(HelloWorld, None),
         }],
     ))
@@ -74,19 +71,34 @@ async fn HelloWorld(res: Res, req: Req) {
     req.query.set_action(&req.user, AskGemini).await;
 }
 
-#[action]
-async fn AskGemini(res: Res, req: Req) {
-    let text: String = req.data.get_value();
-    match ask_gemini(text).await {
-        Ok(response) => {
-            for part in response.candidates[0].content.parts.clone() {
-                res.send(TextModel::new(&req.user, &part.text)).await;
-            }
-        }
-        Err(err) => {
-            res.send(TextModel::new(&req.user, &err.to_string())).await;
+struct AskGemini;
+
+#[russenger::async_trait]
+impl FallibleAction for AskGemini {
+    async fn try_execute(&self, res: Res, req: Req) -> ActionResult {
+        let text: String = req.data.get_value();
+        let response = ask_gemini(text).await?;
+        let candidate = response.candidates.first().ok_or("Gemini sent no answer")?;
+        for part in &candidate.content.parts {
+            res.send(TextModel::new(&req.user, &part.text))
+                .await
+                .into_result()?;
         }
-    };
+        Ok(())
+    }
+
+    fn path(&self) -> String {
+        "AskGemini".to_owned()
+    }
 }
 
-russenger_app!(Main, HelloWorld, AskGemini);
+#[russenger::main]
+async fn main() {
+    russenger::App::new()
+        .action(Main)
+        .action(HelloWorld)
+        .action(AskGemini)
+        .on_error(ErrorMessage::new("Gemini is not available, please try again later."))
+        .launch()
+        .await;
+}
@@ -23,9 +23,10 @@ async fn Main(res: Res, req: Req) {
         elements.push(element);
     }
 
-    let generic = GenericModel::new(&req.user, elements, req.data.get_page());
+    let pagination = Pagination::new(req.data.get_page(), elements.len(), 10);
+    let generic = GenericModel::new(&req.user, elements, Some(pagination.get_page()));
     res.send(generic).await; // Send only 10 element
-    Main.next(res, req).await; // Send next 10 element
+    Main.paginate(res, req, pagination).await; // Send Previous, Page x of y and Next
 }
 
 russenger_app!(Main);
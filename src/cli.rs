@@ -1,10 +1,11 @@
 //! The `cli` module contains the command line interface for the application.
 //!
-//! It includes the `command_handler` function that starts the server.
+//! It includes the `launch` function that runs the command given on the command line for an `App`.
 //!
 //! # Functions
 //!
-//! * `command_handler`: This function starts the server. It first checks if the `ACTION_REGISTRY` contains the `Main` action. If not, it panics. Then it sets up CORS, reads the `PORT` and `HOST` environment variables, and starts the server with these settings.
+//! * `launch`: This function runs the `runserver` or `migrate` command.
+//! * `run_server`: This function starts the server. It first checks if the `App` contains the `Main` action. If not, it panics. Then it reads the `PORT` and `HOST` environment variables, unless the `App` sets them, and starts the server with these settings. Once the server is stopped, it waits for the pending actions to finish.
 //!
 //! # Examples
 //!
@@ -12,41 +13,45 @@
 //!
 //! ```rust
 //! use russenger::cli::launch;
+//! use russenger::prelude::*;
+//!
+//! #[action]
+//! async fn Main(res: Res, req: Req) {}
 //!
 //! #[russenger::main]
 //! async fn main() {
-//!     launch().await;
+//!     launch(russenger::App::new().action(Main)).await;
 //! }
 //! ```
 use actix_files as fs;
-use actix_web::{web, App, HttpServer};
+use actix_web::HttpServer;
 use dotenv::dotenv;
 
-use crate::core::{
-    action::ACTION_REGISTRY,
-    app_state::AppState,
-    services::{webhook_core, webhook_verify}, // core services
-};
+use crate::core::{app::App, app_state::AppState};
 use crate::query::Query;
 
 use std::env;
 
-async fn run_server() {
-    if !ACTION_REGISTRY.lock().await.contains_key("Main") {
+pub async fn run_server(app: App) {
+    if !app.actions.contains_key("Main") {
         panic!("'russenger_app!' should containt `Main` action");
     }
-    let app_state = AppState::init().await;
-    let host = env::var("HOST").unwrap_or("0.0.0.0".into());
-    let port = env::var("PORT")
-        .unwrap_or("2453".into())
-        .parse()
-        .unwrap_or(2453);
+    let host = app
+        .host
+        .clone()
+        .unwrap_or(env::var("HOST").unwrap_or("0.0.0.0".into()));
+    let port = app.port.unwrap_or(
+        env::var("PORT")
+            .unwrap_or("2453".into())
+            .parse()
+            .unwrap_or(2453),
+    );
+    let app_state = AppState::init(app).await;
+    let workers = app_state.workers.clone();
     println!("server start on {host}:{port}");
     HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(app_state.clone()))
-            .service(webhook_verify)
-            .service(webhook_core)
+        actix_web::App::new()
+            .configure(|config| app_state.configure(config))
             .service(fs::Files::new("/static", "static").show_files_listing())
     })
     .bind((host.clone(), port))
@@ -54,6 +59,8 @@ async fn run_server() {
     .run()
     .await
     .expect("sever is crashed");
+    println!("server stopped, waiting for the pending actions");
+    workers.shutdown().await;
 }
 
 async fn migrate() {
@@ -79,11 +86,11 @@ fn parser() -> Option<String> {
     }
 }
 
-pub async fn launch() {
+pub async fn launch(app: App) {
     dotenv().ok();
     match parser() {
         Some(option) => match option.as_str() {
-            "runserver" => run_server().await,
+            "runserver" => run_server(app).await,
             "migrate" => migrate().await,
             _ => print_usage(),
         },
@@ -1,31 +1,103 @@
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-
-use tokio::sync::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
 use super::{request::Req, response::Res};
 use crate::response_models::data::Data;
+use crate::response_models::pagination::Pagination;
 use crate::response_models::payload::Payload;
 use crate::response_models::quick_replies::{QuickReply, QuickReplyModel};
 
-pub struct ActionLock {
-    pub locked_users: Arc<Mutex<HashSet<String>>>,
+/// The error returned by a fallible action, any error can be turned into it with `?`.
+pub type ActionError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The result of `Action::try_execute`.
+pub type ActionResult = Result<(), ActionError>;
+
+/// A unit of work queued for a user, usually the dispatch of one incoming event.
+pub type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The `Mailbox` struct keeps a FIFO queue of pending jobs for each user.
+///
+/// Jobs of the same user run one after the other, in the order they were pushed, while jobs of different users run in parallel.
+///
+/// # Methods
+///
+/// * `push`: Queues a job for a user. It returns `true` when the user had no running job, the caller is then in charge of draining the queue.
+/// * `pop`: Takes the next job of a user. It returns `None` and releases the user's queue once it is empty.
+/// * `release`: Drops the pending jobs of a user and releases the user's queue.
+/// * `guard`: Returns a `MailboxGuard` draining the queue of a user, the queue is released even if the draining stops half way.
+#[derive(Clone, Default)]
+pub struct Mailbox {
+    pub queues: Arc<Mutex<HashMap<String, VecDeque<Job>>>>,
 }
 
-impl ActionLock {
-    pub async fn lock(&self, user: &str) -> bool {
-        let mut locked_user = self.locked_users.lock().await;
-        if !locked_user.contains(user) {
-            locked_user.insert(user.to_string());
-            true
-        } else {
-            false
+impl Mailbox {
+    fn queues(&self) -> MutexGuard<'_, HashMap<String, VecDeque<Job>>> {
+        self.queues.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn push(&self, user: &str, job: Job) -> bool {
+        let mut queues = self.queues();
+        match queues.get_mut(user) {
+            Some(queue) => {
+                queue.push_back(job);
+                false
+            }
+            None => {
+                queues.insert(user.to_owned(), VecDeque::from([job]));
+                true
+            }
         }
     }
 
-    pub async fn unlock(&self, user: &str) {
-        let mut locked_users = self.locked_users.lock().await;
-        locked_users.remove(user);
+    pub fn pop(&self, user: &str) -> Option<Job> {
+        let mut queues = self.queues();
+        let job = queues.get_mut(user).and_then(|queue| queue.pop_front());
+        if job.is_none() {
+            queues.remove(user);
+        }
+        job
+    }
+
+    pub fn release(&self, user: &str) {
+        self.queues().remove(user);
+    }
+
+    pub fn guard(&self, user: &str) -> MailboxGuard {
+        MailboxGuard {
+            mailbox: self.clone(),
+            user: user.to_owned(),
+            released: false,
+        }
+    }
+}
+
+/// `MailboxGuard` drains the queue of a user.
+///
+/// If it is dropped before the queue is empty, because a job panicked or the draining was cancelled, the queue is released,
+/// so the next event of the user starts a new draining instead of waiting forever.
+pub struct MailboxGuard {
+    mailbox: Mailbox,
+    user: String,
+    released: bool,
+}
+
+impl MailboxGuard {
+    /// Takes the next job of the user, the queue is released once it returns `None`.
+    pub fn pop(&mut self) -> Option<Job> {
+        let job = self.mailbox.pop(&self.user);
+        self.released = job.is_none();
+        job
+    }
+}
+
+impl Drop for MailboxGuard {
+    fn drop(&mut self) {
+        if !self.released {
+            self.mailbox.release(&self.user);
+        }
     }
 }
 
@@ -36,8 +108,11 @@ impl ActionLock {
 /// # Methods
 ///
 /// * `execute`: This method is called when a request is received with the action's path. It takes a `Res` and a `Req` as arguments, which represent the response and request respectively.
+/// * `try_execute`: The fallible version of `execute`, its error is logged and given to the error handler of the `App`. It runs `execute` by default.
 /// * `path`: This method returns the path associated with the action.
 ///
+/// The `#[action]` macro implements `execute`. A fallible action implements `FallibleAction` instead.
+///
 /// # Examples
 ///
 /// ```rust
@@ -56,6 +131,11 @@ impl ActionLock {
 pub trait Action: Send + Sync {
     async fn execute(&self, res: Res, req: Req);
 
+    async fn try_execute(&self, res: Res, req: Req) -> ActionResult {
+        self.execute(res, req).await;
+        Ok(())
+    }
+
     fn path(&self) -> String;
 
     async fn next(&self, res: Res, req: Req) {
@@ -75,39 +155,172 @@ pub trait Action: Send + Sync {
         );
         res.send(quick_reply).await;
     }
+
+    /// Sends the navigation quick replies of `pagination`, they run this action again on another page with the same `req.data` value.
+    ///
+    /// Nothing is sent when the list fits in one page.
+    async fn paginate(&self, res: Res, req: Req, pagination: Pagination) {
+        let value: serde_json::Value = req.data.get_value();
+        let quick_replies = pagination.quick_replies(self, value);
+        if !quick_replies.is_empty() {
+            let navigation = QuickReplyModel::new(&req.user, "Navigation", quick_replies);
+            res.send(navigation).await;
+        }
+    }
+}
+
+/// The `FallibleAction` trait defines an action returning a `Result`, every type implementing it is an `Action`.
+///
+/// The error of `try_execute` is logged and given to the error handler of the `App`.
+///
+/// # Methods
+///
+/// * `try_execute`: This method is called when a request is received with the action's path.
+/// * `path`: This method returns the path associated with the action.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// struct Weather;
+///
+/// #[russenger::async_trait]
+/// impl FallibleAction for Weather {
+///     async fn try_execute(&self, res: Res, req: Req) -> ActionResult {
+///         let city: String = req.data.get_value();
+///         let forecast = reqwest::get(format!("https://wttr.in/{city}?format=3")).await?.text().await?;
+///         res.send(TextModel::new(&req.user, &forecast)).await.into_result()?;
+///         Ok(())
+///     }
+///
+///     fn path(&self) -> String {
+///         "Weather".to_owned()
+///     }
+/// }
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {
+///     req.query.set_action(&req.user, Weather).await;
+/// }
+/// ```
+#[async_trait::async_trait]
+pub trait FallibleAction: Send + Sync {
+    async fn try_execute(&self, res: Res, req: Req) -> ActionResult;
+
+    fn path(&self) -> String;
+}
+
+#[async_trait::async_trait]
+impl<A: FallibleAction> Action for A {
+    async fn execute(&self, res: Res, req: Req) {
+        if let Err(error) = FallibleAction::try_execute(self, res, req).await {
+            eprintln!(
+                "the action {:?} failed: {error}",
+                FallibleAction::path(self)
+            );
+        }
+    }
+
+    async fn try_execute(&self, res: Res, req: Req) -> ActionResult {
+        FallibleAction::try_execute(self, res, req).await
+    }
+
+    fn path(&self) -> String {
+        FallibleAction::path(self)
+    }
 }
 
-type ActionRegistryType = Arc<Mutex<HashMap<String, Box<dyn Action>>>>;
+/// `Hook` names the webhook events that are not routed to the user's action.
+///
+/// # Variants
+///
+/// * `Delivery` - The messages sent by the page were delivered, see `req.delivery`.
+/// * `Read` - The messages sent by the page were read, see `req.read`.
+/// * `Echo` - A message was sent by the page, see `req.echo`. Echoes are never routed to the user's action.
+/// * `Reaction` - The user reacted to a message or removed a reaction, see `req.reaction`.
+/// * `Handover` - The owner of the conversation changed, see `req.handover`.
+/// * `Standby` - The user sent a message while another app owns the conversation.
+///
+/// The action handling an event is registered with `App::hook`, an event without an action is ignored.
+///
+/// # Examples
+///
+/// Logging the read receipts:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn OnRead(res: Res, req: Req) {
+///     if let Some(read) = req.read {
+///         println!("{} read until {}", req.user, read.get_watermark());
+///     }
+/// }
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {}
+///
+/// russenger_app!(Main; Hook::Read => OnRead);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hook {
+    Delivery,
+    Read,
+    Echo,
+    Reaction,
+    Handover,
+    Standby,
+}
 
-lazy_static::lazy_static! {
-    /// `ACTION_REGISTRY` is a thread-safe map that stores all the actions available in the application.
-    ///
-    /// Each action is associated with a path, and when a request is received with that path, the corresponding action's `execute` method is called.
-    ///
-    /// The `ACTION_REGISTRY` is initialized with an empty map when the application starts, and actions are added to it using the `create_action!` macro.
-    ///
-    /// # Examples
-    ///
-    /// Adding an action to the `ACTION_REGISTRY`:
-    /// ```rust
-    /// use russenger::{ACTION_REGISTRY, Action};
-    ///
-    /// use russenger::prelude::*;
-    ///
-    /// #[action]
-    /// async fn Main(res: Res, req: Req) {
-    ///     let message: String = req.data.get_value();
-    ///
-    ///     if message == "Hello" {
-    ///         res.send(TextModel::new(&req.user, "Hello, welcome to our bot!")).await;
-    ///     }
-    /// }
-    ///
-    /// #[russenger::main]
-    /// async fn main() {
-    ///     ACTION_REGISTRY.lock().await.insert(Main.path(), Box::new(Main));
-    /// }
-    /// ```
-    pub static ref ACTION_REGISTRY: ActionRegistryType = Arc::new(Mutex::new(HashMap::new()));
-    pub static ref ACTION_LOCK: ActionLock = ActionLock { locked_users: Arc::new(Mutex::new(HashSet::new()))};
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    fn job(log: &Arc<Mutex<Vec<usize>>>, id: usize) -> Job {
+        let log = log.clone();
+        Box::pin(async move { log.lock().unwrap().push(id) })
+    }
+
+    #[actix_web::test]
+    async fn the_jobs_of_a_user_run_in_order() {
+        let mailbox = Mailbox::default();
+        let log = Arc::default();
+
+        assert!(mailbox.push("user", job(&log, 1)));
+        assert!(!mailbox.push("user", job(&log, 2)));
+        assert!(mailbox.push("other", job(&log, 10)));
+        assert!(!mailbox.push("user", job(&log, 3)));
+
+        let mut guard = mailbox.guard("user");
+        while let Some(job) = guard.pop() {
+            job.await;
+        }
+        assert_eq!(*log.lock().unwrap(), vec![1, 2, 3]);
+        assert!(mailbox.push("user", job(&log, 4)));
+        assert!(!mailbox.push("other", job(&log, 11)));
+    }
+
+    #[actix_web::test]
+    async fn the_queue_is_released_after_a_panic() {
+        let mailbox = Mailbox::default();
+        let log = Arc::default();
+        mailbox.push("user", Box::pin(async { panic!("the action failed") }));
+        mailbox.push("user", job(&log, 2));
+
+        let drain = async {
+            let mut guard = mailbox.guard("user");
+            while let Some(job) = guard.pop() {
+                job.await;
+            }
+        };
+        assert!(AssertUnwindSafe(drain).catch_unwind().await.is_err());
+
+        assert!(mailbox.push("user", job(&log, 3)));
+        assert!(log.lock().unwrap().is_empty());
+    }
 }
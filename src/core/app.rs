@@ -0,0 +1,294 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::action::{Action, Hook};
+use super::app_state::AppState;
+use super::command::Command;
+use super::error_handler::ErrorHandler;
+use super::middleware::Middleware;
+use crate::cli;
+
+/// The values shared with every action through `req.state`, one value per type.
+pub type States = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+/// The `App` struct holds everything a bot is made of: its actions, the actions handling the `Hook` events, the state shared with the actions and the server configuration.
+///
+/// Several `App` can live in the same process, each one only runs its own actions.
+///
+/// # Methods
+///
+/// * `new`: Creates an empty `App`.
+/// * `action`: Registers an action, the `Main` action is required.
+/// * `hook`: Registers the action handling a `Hook` event.
+/// * `not_found`: Sets the action run when a request targets an action that is not registered.
+/// * `invalid_payload`: Sets the action run when the payload of a button or a quick reply can't be parsed.
+/// * `rejected_payload`: Sets the action run when the signature of a payload is missing or wrong.
+/// * `on_error`: Sets the `ErrorHandler` of the errors returned by the actions.
+/// * `command`: Routes the text messages matching a pattern to an action, before the action stored for the user.
+/// * `middleware`: Wraps every action with a `Middleware`.
+/// * `action_middleware`: Wraps one action with a `Middleware`.
+/// * `state`: Shares a value with the actions, they read it with `req.state`.
+/// * `ask_timeout`: Sets how long `req.ask` waits for the answer of the user.
+/// * `host`, `port`, `workers`: Override the `HOST`, `PORT` and `WORKERS` env variables.
+/// * `launch`: Runs the `runserver` or `migrate` command given on the command line.
+/// * `run`: Starts the server.
+/// * `into_state`: Creates the `AppState` used to embed the bot in an existing actix-web server.
+///
+/// # Examples
+///
+/// Building the bot without the `russenger_app!` macro:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// struct Config {
+///     greeting: String,
+/// }
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {
+///     let greeting = req.state::<Config>().map(|config| config.greeting.as_str());
+///     res.send(TextModel::new(&req.user, greeting.unwrap_or("Hello"))).await;
+/// }
+///
+/// #[action]
+/// async fn OnRead(res: Res, req: Req) {}
+///
+/// #[russenger::main]
+/// async fn main() {
+///     russenger::App::new()
+///         .action(Main)
+///         .hook(Hook::Read, OnRead)
+///         .state(Config { greeting: "Welcome!".into() })
+///         .port(8080)
+///         .launch()
+///         .await;
+/// }
+/// ```
+///
+/// Embedding the bot in an existing actix-web server:
+///
+/// ```rust,no_run
+/// use actix_web::{HttpServer, App};
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {}
+///
+/// #[russenger::main]
+/// async fn main() -> std::io::Result<()> {
+///     let state = russenger::App::new().action(Main).into_state().await;
+///     HttpServer::new(move || {
+///         let state = state.clone();
+///         App::new().configure(|config| state.configure(config))
+///     })
+///     .bind(("0.0.0.0", 8080))?
+///     .run()
+///     .await
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct App {
+    pub(crate) actions: HashMap<String, Arc<dyn Action>>,
+    pub(crate) hooks: HashMap<Hook, Arc<dyn Action>>,
+    pub(crate) not_found: Option<Arc<dyn Action>>,
+    pub(crate) invalid_payload: Option<Arc<dyn Action>>,
+    pub(crate) rejected_payload: Option<Arc<dyn Action>>,
+    pub(crate) error_handler: Option<Arc<dyn ErrorHandler>>,
+    pub(crate) commands: Vec<Command>,
+    pub(crate) middlewares: Vec<Arc<dyn Middleware>>,
+    pub(crate) action_middlewares: HashMap<String, Vec<Arc<dyn Middleware>>>,
+    pub(crate) states: States,
+    pub(crate) ask_timeout: Option<Duration>,
+    pub(crate) host: Option<String>,
+    pub(crate) port: Option<u16>,
+    pub(crate) workers: Option<usize>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an action, the requests are routed to it by its `path`.
+    pub fn action<A: Action + 'static>(mut self, action: A) -> Self {
+        self.actions.insert(action.path(), Arc::new(action));
+        self
+    }
+
+    /// Registers the action handling a `Hook` event, an event without an action is ignored.
+    pub fn hook<A: Action + 'static>(mut self, hook: Hook, action: A) -> Self {
+        self.hooks.insert(hook, Arc::new(action));
+        self
+    }
+
+    /// Sets the action run when a request targets a path without action, like a button created before an action was renamed.
+    ///
+    /// The missing path is logged, and the request is dropped if no `not_found` action is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {}
+    ///
+    /// #[action]
+    /// async fn NotFound(res: Res, req: Req) {
+    ///     res.send(TextModel::new(&req.user, "This option is no longer available.")).await;
+    ///     Main.execute(res, req).await;
+    /// }
+    ///
+    /// russenger::App::new().action(Main).not_found(NotFound);
+    /// ```
+    pub fn not_found<A: Action + 'static>(mut self, action: A) -> Self {
+        self.not_found = Some(Arc::new(action));
+        self
+    }
+
+    /// Sets the action run when the payload of a button or a quick reply is not a valid `Payload`, the raw payload is in `req.data`.
+    ///
+    /// The malformed payload is logged, and the request goes to the `Main` action if no `invalid_payload` action is set.
+    pub fn invalid_payload<A: Action + 'static>(mut self, action: A) -> Self {
+        self.invalid_payload = Some(Arc::new(action));
+        self
+    }
+
+    /// Sets the action run when the signature of a payload is missing or wrong, like a payload changed by the user, the raw payload is in `req.data`.
+    ///
+    /// The payloads are signed when the `PAYLOAD_SECRET` env variable is set, see `Payload`.
+    /// The referrals with a plain `ref`, not built by `Payload::to_ref`, are rejected too, the `ref` is then in `req.data`.
+    /// The rejected payload is logged, and the request goes to the `Main` action if no `rejected_payload` action is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {}
+    ///
+    /// #[action]
+    /// async fn Rejected(res: Res, req: Req) {
+    ///     res.send(TextModel::new(&req.user, "This button is no longer valid.")).await;
+    ///     Main.execute(res, req).await;
+    /// }
+    ///
+    /// russenger::App::new().action(Main).rejected_payload(Rejected);
+    /// ```
+    pub fn rejected_payload<A: Action + 'static>(mut self, action: A) -> Self {
+        self.rejected_payload = Some(Arc::new(action));
+        self
+    }
+
+    /// Sets the handler of the errors returned by the actions and the hooks, the errors are logged in any case.
+    pub fn on_error<E: ErrorHandler + 'static>(mut self, error_handler: E) -> Self {
+        self.error_handler = Some(Arc::new(error_handler));
+        self
+    }
+
+    /// Routes the text messages matching `pattern` to an action, instead of the action stored for the user.
+    ///
+    /// The pattern is a regular expression matched without regard to the case and the accents.
+    /// The commands are tried in the order they were registered, the first match wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is not a valid regular expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {}
+    ///
+    /// #[action]
+    /// async fn Help(res: Res, req: Req) {}
+    ///
+    /// russenger::App::new()
+    ///     .action(Main)
+    ///     .action(Help)
+    ///     .command("^(menu|start over|stop)$", Main)
+    ///     .command("^(help|aide)$", Help);
+    /// ```
+    pub fn command<A: Action>(mut self, pattern: &str, action: A) -> Self {
+        match Command::new(pattern, action.path()) {
+            Ok(command) => self.commands.push(command),
+            Err(err) => panic!("invalid command pattern {pattern:?}: {err}"),
+        }
+        self
+    }
+
+    /// Wraps every action with a middleware, the middlewares run in the order they were registered.
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Wraps one action with a middleware, it runs after the middlewares wrapping every action.
+    pub fn action_middleware<A: Action, M: Middleware + 'static>(
+        mut self,
+        action: A,
+        middleware: M,
+    ) -> Self {
+        let middlewares = self.action_middlewares.entry(action.path()).or_default();
+        middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Shares a value with the actions, a second value of the same type replaces the first one.
+    pub fn state<T: Send + Sync + 'static>(mut self, state: T) -> Self {
+        self.states.insert(TypeId::of::<T>(), Arc::new(state));
+        self
+    }
+
+    /// Sets how long `req.ask` waits for the answer of the user, 5 minutes by default.
+    pub fn ask_timeout(mut self, ask_timeout: Duration) -> Self {
+        self.ask_timeout = Some(ask_timeout);
+        self
+    }
+
+    /// Sets the host the server listens on, the `HOST` env variable is used otherwise.
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = Some(host.to_owned());
+        self
+    }
+
+    /// Sets the port the server listens on, the `PORT` env variable is used otherwise.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Sets the number of background workers running the actions, the `WORKERS` env variable is used otherwise.
+    ///
+    /// Each worker runs the actions on its own thread, see `WorkerPool`.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Runs the command given on the command line: `runserver` or `migrate`.
+    pub async fn launch(self) {
+        cli::launch(self).await;
+    }
+
+    /// Starts the server.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Main` action is not registered.
+    pub async fn run(self) {
+        cli::run_server(self).await;
+    }
+
+    /// Connects to the database and starts the workers, the returned `AppState` serves the webhook of this `App`.
+    pub async fn into_state(self) -> AppState {
+        AppState::init(self).await
+    }
+}
@@ -1,13 +1,105 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::web;
+use dotenv::dotenv;
+
+use super::{
+    action::{Action, Hook, Mailbox},
+    app::{App, States},
+    command::Command,
+    conversation::{Conversations, ASK_TIMEOUT},
+    deduplicator::Deduplicator,
+    error_handler::ErrorHandler,
+    middleware::Middleware,
+    page_tokens::{PageTokens, PAGE_TOKEN_TTL},
+    services::{webhook_core, webhook_verify},
+    thread_owners::ThreadOwners,
+    worker_pool::WorkerPool,
+};
 use crate::query::Query;
+use crate::response_models::payload::Payload;
 
+/// The `AppState` struct is the state of a running `App`, shared by the webhook handlers.
+///
+/// # Methods
+///
+/// * `init`: Connects to the database and starts the workers of an `App`.
+/// * `configure`: Registers the webhook routes of the `App` on an actix-web app.
 #[derive(Clone)]
 pub struct AppState {
     pub query: Query,
+    pub workers: WorkerPool,
+    pub deduplicator: Deduplicator,
+    pub thread_owners: ThreadOwners,
+    pub page_tokens: PageTokens,
+    pub mailbox: Mailbox,
+    pub conversations: Conversations,
+    pub(crate) actions: Arc<HashMap<String, Arc<dyn Action>>>,
+    pub(crate) hooks: Arc<HashMap<Hook, Arc<dyn Action>>>,
+    pub(crate) not_found: Option<Arc<dyn Action>>,
+    pub(crate) invalid_payload: Option<Arc<dyn Action>>,
+    pub(crate) rejected_payload: Option<Arc<dyn Action>>,
+    pub(crate) error_handler: Option<Arc<dyn ErrorHandler>>,
+    pub(crate) commands: Arc<Vec<Command>>,
+    pub(crate) middlewares: Arc<Vec<Arc<dyn Middleware>>>,
+    pub(crate) action_middlewares: Arc<HashMap<String, Vec<Arc<dyn Middleware>>>>,
+    pub(crate) states: Arc<States>,
 }
 
 impl AppState {
-    pub async fn init() -> Self {
+    pub async fn init(app: App) -> Self {
+        dotenv().ok();
         let query: Query = Query::new().await;
-        Self { query }
+        let size = app.workers.unwrap_or_else(|| {
+            env::var("WORKERS")
+                .unwrap_or("8".into())
+                .parse()
+                .unwrap_or(8)
+        });
+        let workers = WorkerPool::new(size);
+        let window = env::var("DEDUPE_WINDOW")
+            .unwrap_or("3600".into())
+            .parse()
+            .unwrap_or(3600);
+        let persist = env::var("DEDUPE_PERSIST").is_ok_and(|persist| persist.eq("true"));
+        let deduplicator =
+            Deduplicator::new(Duration::from_secs(window), persist.then(|| query.clone()));
+        let thread_owners = ThreadOwners::new(Some(query.clone()));
+        let page_tokens = PageTokens::new(query.clone(), PAGE_TOKEN_TTL);
+        if !Payload::is_signed() {
+            eprintln!(
+                "warning: PAYLOAD_SECRET is not set, the payloads are not signed and the users can change them"
+            );
+        }
+        Self {
+            query,
+            workers,
+            deduplicator,
+            thread_owners,
+            page_tokens,
+            mailbox: Mailbox::default(),
+            conversations: Conversations::new(app.ask_timeout.unwrap_or(ASK_TIMEOUT)),
+            actions: Arc::new(app.actions),
+            hooks: Arc::new(app.hooks),
+            not_found: app.not_found,
+            invalid_payload: app.invalid_payload,
+            rejected_payload: app.rejected_payload,
+            error_handler: app.error_handler,
+            commands: Arc::new(app.commands),
+            middlewares: Arc::new(app.middlewares),
+            action_middlewares: Arc::new(app.action_middlewares),
+            states: Arc::new(app.states),
+        }
+    }
+
+    /// Registers the `GET` and `POST` `/webhook` routes, to be used with `actix_web::App::configure`.
+    pub fn configure(&self, config: &mut web::ServiceConfig) {
+        config
+            .app_data(web::Data::new(self.clone()))
+            .service(webhook_verify)
+            .service(webhook_core);
     }
 }
@@ -0,0 +1,58 @@
+use regex::{Regex, RegexBuilder};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Removes the accents of a text, `"Menú"` becomes `"Menu"`.
+pub fn strip_diacritics(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
+
+/// A `Command` routes the text messages matching a pattern to an action, whatever the action stored for the user.
+///
+/// The pattern is a regular expression matched without regard to the case and the accents, so `"^menu$"` matches `"Menu"` and `"MENÚ"`.
+///
+/// # Methods
+///
+/// * `new`: Compiles the pattern of a command.
+/// * `is_match`: Returns `true` if the text matches the pattern.
+/// * `get_path`: Returns the path of the action of the command.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::core::command::Command;
+///
+/// let command = Command::new("^(menu|start over)$", "Main".into()).unwrap();
+/// assert!(command.is_match("MENÚ"));
+/// assert!(command.is_match("Start Over "));
+/// assert!(!command.is_match("show me the menu"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Command {
+    pattern: Regex,
+    path: String,
+}
+
+impl Command {
+    /// Compiles the pattern of a command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is not a valid regular expression.
+    pub fn new(pattern: &str, path: String) -> Result<Self, regex::Error> {
+        let pattern = RegexBuilder::new(&strip_diacritics(pattern))
+            .case_insensitive(true)
+            .build()?;
+        Ok(Self { pattern, path })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.pattern.is_match(strip_diacritics(text).trim())
+    }
+
+    pub fn get_path(&self) -> String {
+        self.path.clone()
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use tokio::sync::{oneshot, Notify};
+
+/// How long `req.ask` waits for the answer of the user by default.
+pub const ASK_TIMEOUT: Duration = Duration::from_secs(300);
+
+tokio::task_local! {
+    static SUSPENDED: Arc<Notify>;
+}
+
+/// Runs the events of a user, `suspended` is notified when one of them starts waiting for an answer in `req.ask`.
+pub(crate) async fn suspendable<F: Future>(suspended: Arc<Notify>, events: F) -> F::Output {
+    SUSPENDED.scope(suspended, events).await
+}
+
+fn suspend() {
+    SUSPENDED.try_with(|suspended| suspended.notify_one()).ok();
+}
+
+/// The error returned by `req.ask` when the user sent a command, clicked a button or sent attachments instead of answering.
+///
+/// The event of the user is routed as usual once the action returned, the error handler of the `App` is not called.
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the question was interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// The `Conversations` struct keeps the actions waiting for the next message of a user, see `Req::ask`.
+///
+/// The webhook gives the next text message of the user to the waiting action instead of routing it,
+/// the message does not wait behind the action in the user's queue.
+///
+/// # Methods
+///
+/// * `new`: Creates the conversations, waiting at most `timeout` for an answer.
+/// * `wait`: Registers an action waiting for the next message of a user, the worker running it is released meanwhile.
+/// * `is_waiting`: Returns `true` if an action is waiting for the next message of a user.
+/// * `resume`: Gives a message to the action waiting for it. It returns `false` if no action is waiting.
+/// * `interrupt`: Stops the wait of the action of a user, `req.ask` then returns `Interrupted`.
+/// * `cancel`: Forgets the action of a user which stopped waiting.
+#[derive(Clone)]
+pub struct Conversations {
+    waiting: Arc<Mutex<HashMap<String, oneshot::Sender<String>>>>,
+    timeout: Duration,
+}
+
+impl Default for Conversations {
+    fn default() -> Self {
+        Self::new(ASK_TIMEOUT)
+    }
+}
+
+impl Conversations {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            waiting: Arc::default(),
+            timeout,
+        }
+    }
+
+    fn waiting(&self) -> MutexGuard<'_, HashMap<String, oneshot::Sender<String>>> {
+        self.waiting.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn wait(&self, user: &str) -> oneshot::Receiver<String> {
+        let (sender, receiver) = oneshot::channel();
+        self.waiting().insert(user.to_owned(), sender);
+        suspend();
+        receiver
+    }
+
+    pub fn is_waiting(&self, user: &str) -> bool {
+        self.waiting()
+            .get(user)
+            .is_some_and(|sender| !sender.is_closed())
+    }
+
+    pub fn resume(&self, user: &str, message: String) -> bool {
+        match self.waiting().remove(user) {
+            Some(sender) => sender.send(message).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn interrupt(&self, user: &str) {
+        self.waiting().remove(user);
+    }
+
+    pub fn cancel(&self, user: &str) {
+        let mut waiting = self.waiting();
+        if waiting.get(user).is_some_and(|sender| sender.is_closed()) {
+            waiting.remove(user);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn waiting_for_an_answer_suspends_the_events() {
+        let conversations = Conversations::default();
+        let suspended = Arc::new(Notify::new());
+        let mut answer = None;
+        suspendable(suspended.clone(), async {
+            answer = Some(conversations.wait("user"));
+        })
+        .await;
+        suspended.notified().await;
+
+        assert!(conversations.is_waiting("user"));
+        assert!(conversations.resume("user", "Bob".to_owned()));
+        assert_eq!(answer.unwrap().await.unwrap(), "Bob");
+        assert!(!conversations.is_waiting("user"));
+    }
+
+    #[actix_web::test]
+    async fn an_interrupted_question_gets_no_answer() {
+        let conversations = Conversations::default();
+        let answer = conversations.wait("user");
+        conversations.interrupt("user");
+
+        assert!(answer.await.is_err());
+        assert!(!conversations.resume("user", "Bob".to_owned()));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::Mutex;
+
+use crate::query::Query;
+
+/// The `Deduplicator` struct remembers the webhook events already processed, so an event redelivered by Facebook does not run an action twice.
+///
+/// Events are kept in memory for `window`. When a `Query` is given, they are also recorded in the `russenger_event` table,
+/// which survives restarts and is shared by every process using the same database.
+/// When the event can't be recorded, like during a database outage, the in-memory result is used.
+///
+/// # Methods
+///
+/// * `new`: This method creates a new `Deduplicator` with the given window and the optional `Query` used for persistence.
+/// * `is_duplicate`: This method records an event and returns `true` if it was already seen within the window.
+#[derive(Clone)]
+pub struct Deduplicator {
+    seen: Arc<Mutex<Seen>>,
+    window: Duration,
+    query: Option<Query>,
+}
+
+impl Deduplicator {
+    pub fn new(window: Duration, query: Option<Query>) -> Self {
+        Self {
+            seen: Arc::default(),
+            window,
+            query,
+        }
+    }
+
+    pub async fn is_duplicate(&self, event_id: &str) -> bool {
+        {
+            let mut seen = self.seen.lock().await;
+            seen.evict(self.window);
+            if !seen.insert(event_id) {
+                return true;
+            }
+        }
+        match &self.query {
+            Some(query) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as i64;
+                let expired_before = now - self.window.as_millis() as i64;
+                query.save_event(event_id, now, expired_before).await == Some(false)
+            }
+            None => false,
+        }
+    }
+}
+
+/// The events seen in memory, `order` keeps them by arrival so the expired ones are evicted from its front.
+#[derive(Default)]
+struct Seen {
+    ids: HashSet<String>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl Seen {
+    fn evict(&mut self, window: Duration) {
+        while let Some((time, event_id)) = self.order.front() {
+            if time.elapsed() < window {
+                break;
+            }
+            self.ids.remove(event_id);
+            self.order.pop_front();
+        }
+    }
+
+    /// Records an event, it returns `false` if it was already seen.
+    fn insert(&mut self, event_id: &str) -> bool {
+        if !self.ids.insert(event_id.to_owned()) {
+            return false;
+        }
+        self.order.push_back((Instant::now(), event_id.to_owned()));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::query::DB;
+
+    async fn sqlite() -> Query {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        Query {
+            db: DB::Sqlite(pool),
+        }
+    }
+
+    #[actix_web::test]
+    async fn remembers_the_events_within_the_window() {
+        let deduplicator = Deduplicator::new(Duration::from_secs(60), None);
+        assert!(!deduplicator.is_duplicate("m1").await);
+        assert!(deduplicator.is_duplicate("m1").await);
+        assert!(!deduplicator.is_duplicate("m2").await);
+
+        let deduplicator = Deduplicator::new(Duration::ZERO, None);
+        assert!(!deduplicator.is_duplicate("m1").await);
+        assert!(!deduplicator.is_duplicate("m1").await);
+    }
+
+    #[test]
+    fn evicts_the_expired_events_in_order() {
+        let mut seen = Seen::default();
+        assert!(seen.insert("m1"));
+        assert!(seen.insert("m2"));
+        assert!(!seen.insert("m1"));
+
+        seen.evict(Duration::from_secs(60));
+        assert_eq!(seen.order.len(), 2);
+        seen.evict(Duration::ZERO);
+        assert!(seen.ids.is_empty() && seen.order.is_empty());
+        assert!(seen.insert("m1"));
+    }
+
+    #[actix_web::test]
+    async fn a_recorded_event_is_a_duplicate() {
+        let query = sqlite().await;
+        assert!(query.migrate().await);
+        assert_eq!(query.save_event("m1", 1, 0).await, Some(true));
+        assert_eq!(query.save_event("m1", 1, 0).await, Some(false));
+
+        let first = Deduplicator::new(Duration::from_secs(60), Some(query.clone()));
+        let second = Deduplicator::new(Duration::from_secs(60), Some(query));
+        assert!(!first.is_duplicate("m2").await);
+        assert!(second.is_duplicate("m2").await);
+    }
+
+    #[actix_web::test]
+    async fn a_failing_database_falls_back_to_memory() {
+        let missing_table = sqlite().await;
+        assert_eq!(missing_table.save_event("m1", 1, 0).await, None);
+        let null = Query { db: DB::Null };
+        assert_eq!(null.save_event("m1", 1, 0).await, None);
+
+        for query in [missing_table, null] {
+            let deduplicator = Deduplicator::new(Duration::from_secs(60), Some(query));
+            assert!(!deduplicator.is_duplicate("m1").await);
+            assert!(deduplicator.is_duplicate("m1").await);
+        }
+    }
+}
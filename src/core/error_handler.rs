@@ -0,0 +1,79 @@
+use super::{action::ActionError, request::Req, response::Res};
+use crate::response_models::text::TextModel;
+
+/// The `ErrorHandler` trait handles the errors returned by the actions, after they were logged.
+///
+/// It is registered with `App::on_error`.
+///
+/// # Methods
+///
+/// * `handle`: This method is called with the `Res` and the `Req` of the failed action, and its error.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// struct Alert;
+///
+/// #[russenger::async_trait]
+/// impl ErrorHandler for Alert {
+///     async fn handle(&self, res: Res, req: Req, error: ActionError) {
+///         res.send(TextModel::new(&req.user, "Oops, please try again later.")).await;
+///         res.send(TextModel::new("admin_id", &format!("{} got {error}", req.user))).await;
+///     }
+/// }
+/// ```
+#[async_trait::async_trait]
+pub trait ErrorHandler: Send + Sync {
+    async fn handle(&self, res: Res, req: Req, error: ActionError);
+}
+
+/// `ErrorMessage` is an `ErrorHandler` sending a friendly message to the user.
+///
+/// # Methods
+///
+/// * `new`: Creates an `ErrorMessage` sending the given text.
+/// * `reset`: Also sets the action of the user back to `Main`, so the next message starts over.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {}
+///
+/// russenger::App::new()
+///     .action(Main)
+///     .on_error(ErrorMessage::new("Something went wrong, let's start over.").reset());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ErrorMessage {
+    text: String,
+    reset: bool,
+}
+
+impl ErrorMessage {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            reset: false,
+        }
+    }
+
+    pub fn reset(mut self) -> Self {
+        self.reset = true;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ErrorHandler for ErrorMessage {
+    async fn handle(&self, res: Res, req: Req, _error: ActionError) {
+        if self.reset {
+            req.query.reset_action(&req.user).await;
+        }
+        res.send(TextModel::new(&req.user, &self.text)).await;
+    }
+}
@@ -0,0 +1,449 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    action::{Action, Job},
+    command::strip_diacritics,
+    request::Req,
+    response::Res,
+};
+use crate::response_models::{
+    data::Data,
+    payload::Payload,
+    quick_replies::{QuickReply, QuickReplyModel},
+    text::TextModel,
+};
+
+type Validator = Arc<dyn Fn(&str) -> Option<Value> + Send + Sync>;
+type Callback<T> = Arc<dyn Fn(Res, Req, T) -> Job + Send + Sync>;
+
+/// Compares two texts without regard to the case and the accents.
+fn same_text(left: &str, right: &str) -> bool {
+    strip_diacritics(left.trim()).to_lowercase() == strip_diacritics(right.trim()).to_lowercase()
+}
+
+fn is_valid_date(text: &str) -> bool {
+    let parts: Vec<&str> = text.split('-').collect();
+    let lengths: Vec<usize> = parts.iter().map(|part| part.len()).collect();
+    let digits = parts
+        .iter()
+        .all(|part| part.bytes().all(|byte| byte.is_ascii_digit()));
+    if lengths != [4, 2, 2] || !digits {
+        return false;
+    }
+    let [year, month, day] = [0, 1, 2].map(|i| parts[i].parse::<u32>().unwrap_or_default());
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// A `Field` is a question of a `Form`, with the validator of the answer.
+///
+/// The validated answer is stored under the name of the field, so the names match the fields of the struct given to `Form::on_complete`.
+///
+/// # Methods
+///
+/// * `text`: Accepts any non empty text.
+/// * `email`: Accepts an email address.
+/// * `phone`: Accepts a phone number of 7 to 15 digits, the spaces, dashes, dots and parentheses are removed.
+/// * `number`: Accepts a number, an integer when there is no decimal part.
+/// * `date`: Accepts a date written `YYYY-MM-DD`.
+/// * `choice`: Accepts one of the options, sent as quick replies.
+/// * `custom`: Accepts the answers for which the validator returns a value.
+/// * `invalid_message`: Replaces the message sent before asking the question again.
+#[derive(Clone)]
+pub struct Field {
+    name: String,
+    question: String,
+    invalid_message: String,
+    choices: Vec<String>,
+    validator: Validator,
+}
+
+impl Field {
+    pub fn custom<V>(name: &str, question: &str, validator: V) -> Self
+    where
+        V: Fn(&str) -> Option<Value> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_owned(),
+            question: question.to_owned(),
+            invalid_message: "This answer is not valid.".to_owned(),
+            choices: Vec::new(),
+            validator: Arc::new(validator),
+        }
+    }
+
+    pub fn text(name: &str, question: &str) -> Self {
+        Self::custom(name, question, |text| {
+            (!text.is_empty()).then(|| Value::from(text))
+        })
+        .invalid_message("Please enter some text.")
+    }
+
+    pub fn email(name: &str, question: &str) -> Self {
+        let email = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").expect("valid email pattern");
+        Self::custom(name, question, move |text| {
+            email.is_match(text).then(|| Value::from(text))
+        })
+        .invalid_message("Please enter a valid email address.")
+    }
+
+    pub fn phone(name: &str, question: &str) -> Self {
+        Self::custom(name, question, |text| {
+            let number: String = text
+                .chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+                .collect();
+            let digits = number.strip_prefix('+').unwrap_or(&number);
+            let valid =
+                (7..=15).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit());
+            valid.then(|| Value::from(number))
+        })
+        .invalid_message("Please enter a valid phone number.")
+    }
+
+    pub fn number(name: &str, question: &str) -> Self {
+        Self::custom(name, question, |text| {
+            let text = text.replace(',', ".");
+            match text.parse::<i64>() {
+                Ok(number) => Some(Value::from(number)),
+                Err(_) => text
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .map(Value::from),
+            }
+        })
+        .invalid_message("Please enter a number.")
+    }
+
+    pub fn date(name: &str, question: &str) -> Self {
+        Self::custom(name, question, |text| {
+            is_valid_date(text).then(|| Value::from(text))
+        })
+        .invalid_message("Please enter a date like 2024-12-31.")
+    }
+
+    pub fn choice(name: &str, question: &str, choices: &[&str]) -> Self {
+        let options: Vec<String> = choices.iter().map(|choice| choice.to_string()).collect();
+        let mut field = Self::custom(name, question, move |text| {
+            let choice = options.iter().find(|choice| same_text(choice, text));
+            choice.map(|choice| Value::from(choice.as_str()))
+        })
+        .invalid_message("Please choose one of the options.");
+        field.choices = choices.iter().map(|choice| choice.to_string()).collect();
+        field
+    }
+
+    pub fn invalid_message(mut self, invalid_message: &str) -> Self {
+        self.invalid_message = invalid_message.to_owned();
+        self
+    }
+}
+
+/// Returns `false` if the answers can't be deserialized into `T`, the missing fields are fine as they are asked later.
+fn fits<T: DeserializeOwned>(answers: &Map<String, Value>) -> bool {
+    match serde_json::from_value::<T>(Value::Object(answers.clone())) {
+        Ok(_) => true,
+        Err(err) => err.to_string().starts_with("missing field"),
+    }
+}
+
+/// The progress of a user in a form, kept in the user's `Session`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FormState {
+    step: usize,
+    answers: Map<String, Value>,
+}
+
+/// A `Form` asks the user its fields one after the other, and gives the answers to a callback once they are all valid.
+///
+/// A form is an `Action` registered in the `App` like the others. Running it starts the form, it then stays the action of the user
+/// until the last answer or the cancellation, and the user's action is set back to `Main`.
+/// An invalid answer is followed by the invalid message of the field and the question again,
+/// like an answer the validator accepts but which doesn't fit the type of the field in `T`.
+/// Running the form with a payload without data, like the button starting it, starts it over.
+///
+/// The answers are kept in the user's `Session`, and deserialized into `T` for the callback.
+///
+/// # Methods
+///
+/// * `new`: Creates a form, the name is the path of its action.
+/// * `field`: Adds a field, the fields are asked in the order they were added.
+/// * `cancel_keyword`: Replaces the `cancel` keyword stopping the form.
+/// * `cancel_message`: Replaces the message sent when the form is cancelled.
+/// * `on_complete`: Sets the callback receiving the answers.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::prelude::*;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Order {
+///     name: String,
+///     phone: String,
+///     quantity: u32,
+///     size: String,
+/// }
+///
+/// async fn save_order(res: Res, req: Req, order: Order) {
+///     let text = format!("Thanks {}, {} {} t-shirt(s) are on their way.", order.name, order.quantity, order.size);
+///     res.send(TextModel::new(&req.user, &text)).await;
+/// }
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {
+///     let order = Form::<Order>::new("Order");
+///     let buttons = vec![Button::Postback { title: "Order".to_owned(), payload: Payload::new(order, None) }];
+///     res.send(ButtonModel::new(&req.user, "Welcome to the shop", buttons)).await;
+/// }
+///
+/// #[russenger::main]
+/// async fn main() {
+///     let order = Form::new("Order")
+///         .field(Field::text("name", "What is your name?"))
+///         .field(Field::phone("phone", "What is your phone number?"))
+///         .field(Field::number("quantity", "How many t-shirts?"))
+///         .field(Field::choice("size", "Which size?", &["S", "M", "L"]))
+///         .on_complete(save_order);
+///     russenger::App::new().action(Main).action(order).launch().await;
+/// }
+/// ```
+pub struct Form<T> {
+    path: String,
+    fields: Vec<Field>,
+    cancel_keyword: String,
+    cancel_message: String,
+    on_complete: Option<Callback<T>>,
+}
+
+impl<T> Clone for Form<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            fields: self.fields.clone(),
+            cancel_keyword: self.cancel_keyword.clone(),
+            cancel_message: self.cancel_message.clone(),
+            on_complete: self.on_complete.clone(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static> Form<T> {
+    pub fn new(name: &str) -> Self {
+        Self {
+            path: name.to_owned(),
+            fields: Vec::new(),
+            cancel_keyword: "cancel".to_owned(),
+            cancel_message: "The form is cancelled.".to_owned(),
+            on_complete: None,
+        }
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    pub fn cancel_keyword(mut self, cancel_keyword: &str) -> Self {
+        self.cancel_keyword = cancel_keyword.to_owned();
+        self
+    }
+
+    pub fn cancel_message(mut self, cancel_message: &str) -> Self {
+        self.cancel_message = cancel_message.to_owned();
+        self
+    }
+
+    pub fn on_complete<F, Fut>(mut self, callback: F) -> Self
+    where
+        F: Fn(Res, Req, T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.on_complete = Some(Arc::new(move |res, req, answers| {
+            Box::pin(callback(res, req, answers))
+        }));
+        self
+    }
+
+    fn session_key(&self) -> String {
+        format!("form:{}", self.path)
+    }
+
+    async fn ask(&self, res: &Res, req: &Req, field: &Field) {
+        if field.choices.is_empty() {
+            res.send(TextModel::new(&req.user, &field.question)).await;
+            return;
+        }
+        let quick_replies = field
+            .choices
+            .iter()
+            .map(|choice| {
+                let payload = Payload::new(self.clone(), Some(Data::new(choice, None)));
+                QuickReply::new(choice, "", payload)
+            })
+            .collect();
+        res.send(QuickReplyModel::new(
+            &req.user,
+            &field.question,
+            quick_replies,
+        ))
+        .await;
+    }
+
+    /// Asks the next field, or completes the form once every field is answered.
+    async fn advance(&self, res: Res, req: Req, state: FormState) {
+        let session = req.session();
+        if let Some(field) = self.fields.get(state.step) {
+            session.set(&self.session_key(), &state).await;
+            self.ask(&res, &req, field).await;
+            return;
+        }
+        session.remove(&self.session_key()).await;
+        req.query.reset_action(&req.user).await;
+        match serde_json::from_value(Value::Object(state.answers)) {
+            Ok(answers) => {
+                if let Some(on_complete) = &self.on_complete {
+                    on_complete(res, req, answers).await;
+                }
+            }
+            Err(err) => {
+                eprintln!("the answers of the form {:?} don't fit: {err}", self.path);
+                res.send(TextModel::new(&req.user, &self.cancel_message))
+                    .await;
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: DeserializeOwned + Send + 'static> Action for Form<T> {
+    async fn execute(&self, res: Res, req: Req) {
+        let session = req.session();
+        let in_form = req.query.get_action(&req.user).await.as_ref() == Some(&self.path);
+        let starting = req.data.is_empty() && !req.is_attachment();
+        let state = match in_form && !starting {
+            true => session.get::<FormState>(&self.session_key()).await,
+            false => None,
+        };
+        let Some(mut state) = state else {
+            req.query.set_action(&req.user, self.clone()).await;
+            self.advance(res, req, FormState::default()).await;
+            return;
+        };
+        let text: String = req.data.get_value();
+        if same_text(&text, &self.cancel_keyword) {
+            session.remove(&self.session_key()).await;
+            req.query.reset_action(&req.user).await;
+            res.send(TextModel::new(&req.user, &self.cancel_message))
+                .await;
+            return;
+        }
+        let Some(field) = self.fields.get(state.step) else {
+            return self.advance(res, req, state).await;
+        };
+        let mut answers = state.answers.clone();
+        let value = (field.validator)(text.trim());
+        match value.filter(|value| {
+            answers.insert(field.name.clone(), value.clone());
+            fits::<T>(&answers)
+        }) {
+            Some(_) => {
+                state.answers = answers;
+                state.step += 1;
+                self.advance(res, req, state).await;
+            }
+            None => {
+                res.send(TextModel::new(&req.user, &field.invalid_message))
+                    .await;
+                self.ask(&res, &req, field).await;
+            }
+        }
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(field: Field, text: &str) -> Option<Value> {
+        (field.validator)(text)
+    }
+
+    #[test]
+    fn dates_are_written_with_digits() {
+        assert!(is_valid_date("2024-02-29"));
+        assert!(!is_valid_date("2023-02-29"));
+        assert!(!is_valid_date("2024-13-01"));
+        assert!(!is_valid_date("2024-01-+5"));
+        assert!(!is_valid_date("+024-01-05"));
+        assert!(!is_valid_date("2024-1-005"));
+        let date = Field::date("date", "When?");
+        assert_eq!(
+            validate(date, "2024-12-31"),
+            Some(Value::from("2024-12-31"))
+        );
+    }
+
+    #[test]
+    fn validators_accept_the_expected_answers() {
+        let email = || Field::email("email", "Email?");
+        assert!(validate(email(), "joe@example.com").is_some());
+        assert!(validate(email(), "joe@example").is_none());
+
+        let phone = || Field::phone("phone", "Phone?");
+        assert_eq!(
+            validate(phone(), "+261 34 12-345.67"),
+            Some(Value::from("+261341234567"))
+        );
+        assert!(validate(phone(), "12345").is_none());
+        assert!(validate(phone(), "0341234abc").is_none());
+
+        let number = || Field::number("number", "Number?");
+        assert_eq!(validate(number(), "-3"), Some(Value::from(-3)));
+        assert_eq!(validate(number(), "2,5"), Some(Value::from(2.5)));
+        assert!(validate(number(), "inf").is_none());
+        assert!(validate(number(), "three").is_none());
+
+        let choice = || Field::choice("size", "Size?", &["Small", "Médium"]);
+        assert_eq!(validate(choice(), " medium "), Some(Value::from("Médium")));
+        assert!(validate(choice(), "large").is_none());
+
+        assert!(validate(Field::text("name", "Name?"), "").is_none());
+    }
+
+    #[test]
+    fn answers_must_fit_the_type_of_the_form() {
+        #[derive(Deserialize)]
+        #[allow(dead_code)]
+        struct Order {
+            name: String,
+            quantity: u32,
+        }
+
+        let mut answers = Map::new();
+        answers.insert("name".into(), Value::from("Joe"));
+        assert!(fits::<Order>(&answers));
+        answers.insert("quantity".into(), Value::from(-3));
+        assert!(!fits::<Order>(&answers));
+        answers.insert("quantity".into(), Value::from(3));
+        assert!(fits::<Order>(&answers));
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::{action::Action, request::Req, response::Res};
+use crate::response_models::data::Data;
+
+/// How many visited actions are kept for each user.
+pub const MAX_HISTORY: usize = 20;
+
+const HISTORY_KEY: &str = "russenger:history";
+
+/// An action visited by the user, with the `Data` it received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub path: String,
+    pub data: Data,
+}
+
+/// Records an action the user navigated to, with a button, a quick reply or a referral, in the history kept in the user's `Session`.
+///
+/// The text messages and the attachments are not recorded, they answer the current action instead of opening another one.
+/// A visit of the same action as the last one replaces it, so a user answering the same action several times goes back in one step.
+pub async fn record(req: &Req, path: &str) {
+    let session = req.session();
+    let mut history: Vec<Visit> = session.get(HISTORY_KEY).await.unwrap_or_default();
+    if history.last().is_some_and(|visit| visit.path == path) {
+        history.pop();
+    }
+    history.push(Visit {
+        path: path.to_owned(),
+        data: req.data.clone(),
+    });
+    if history.len() > MAX_HISTORY {
+        history.remove(0);
+    }
+    session.set(HISTORY_KEY, &history).await;
+}
+
+/// Forgets the current action of the user and returns the previous one, or `None` if there is no previous action.
+pub async fn back(req: &Req) -> Option<Visit> {
+    let session = req.session();
+    let mut history: Vec<Visit> = session.get(HISTORY_KEY).await.unwrap_or_default();
+    history.pop();
+    session.set(HISTORY_KEY, &history).await;
+    history.last().cloned()
+}
+
+/// `Back` runs again the previous action of the user, with the `Data` it received then.
+///
+/// It is handled by the router, so it is used in the payloads of the buttons and quick replies, see `QuickReplyModel::with_back`.
+/// The user goes to `Main` when there is no previous action.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Settings(res: Res, req: Req) {
+///     let buttons = vec![Button::Postback { title: "Back".to_owned(), payload: Payload::new(Back, None) }];
+///     res.send(ButtonModel::new(&req.user, "Settings", buttons)).await;
+/// }
+/// ```
+pub struct Back;
+
+#[async_trait::async_trait]
+impl Action for Back {
+    async fn execute(&self, _res: Res, _req: Req) {}
+
+    fn path(&self) -> String {
+        "Back".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::query::{Query, DB};
+
+    async fn req() -> Req {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let query = Query {
+            db: DB::Sqlite(pool),
+        };
+        assert!(query.migrate().await);
+        Req::new("user", query, Data::default(), "localhost")
+    }
+
+    #[actix_web::test]
+    async fn back_returns_the_previous_visit() {
+        let mut req = req().await;
+        assert!(back(&req).await.is_none());
+
+        record(&req, "Main").await;
+        req.data = Data::new("red", None);
+        record(&req, "Colors").await;
+        record(&req, "Colors").await;
+        record(&req, "Checkout").await;
+
+        let visit = back(&req).await.unwrap();
+        assert_eq!(visit.path, "Colors");
+        assert_eq!(visit.data.get_value::<String>(), "red");
+        assert_eq!(back(&req).await.unwrap().path, "Main");
+        assert!(back(&req).await.is_none());
+        assert!(back(&req).await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn the_history_is_capped() {
+        let req = req().await;
+        for index in 0..MAX_HISTORY + 5 {
+            record(&req, &format!("Page{index}")).await;
+        }
+        let history: Vec<Visit> = req.session().get(HISTORY_KEY).await.unwrap();
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history[0].path, "Page5");
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Deserialize;
+use serde_json::Value;
 
-#[derive(Debug, Default, Deserialize)]
+use crate::response_models::Platform;
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Sender {
     id: String,
 }
@@ -16,13 +19,125 @@ impl QuickReplyPayload {
     }
 }
 
+/// `AttachmentType` is the kind of file or content a user sent.
+///
+/// Stickers are delivered by Facebook as `image` attachments carrying a `sticker_id`,
+/// `Attachment::get_type` reports them as `Sticker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentType {
+    Image,
+    Audio,
+    Video,
+    File,
+    Location,
+    Sticker,
+    Fallback,
+    #[serde(other)]
+    Unknown,
+}
+
+/// `Coordinates` is the position shared by a `location` attachment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct Coordinates {
+    pub lat: f64,
+    pub long: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct AttachmentPayload {
+    url: Option<String>,
+    sticker_id: Option<u64>,
+    coordinates: Option<Coordinates>,
+}
+
+/// `Attachment` represents a file or content sent by the user: an image, an audio, a video, a file, a location or a sticker.
+///
+/// # Methods
+///
+/// * `get_type`: Returns the `AttachmentType` of the attachment.
+/// * `get_url`: Returns the URL of the file, if any.
+/// * `get_sticker_id`: Returns the id of the sticker, if the attachment is a sticker.
+/// * `get_coordinates`: Returns the `Coordinates` of a location attachment.
+///
+/// # Examples
+///
+/// Replying to the user with the URL of the image they sent:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {
+///     for attachment in &req.attachments {
+///         if attachment.get_type() == AttachmentType::Image {
+///             let url = attachment.get_url().unwrap_or_default();
+///             res.send(TextModel::new(&req.user, &format!("Nice picture: {url}"))).await;
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Attachment {
+    #[serde(rename = "type")]
+    r#type: AttachmentType,
+    #[serde(default)]
+    payload: AttachmentPayload,
+}
+
+impl Attachment {
+    pub fn get_type(&self) -> AttachmentType {
+        match self.payload.sticker_id {
+            Some(_) => AttachmentType::Sticker,
+            None => self.r#type,
+        }
+    }
+
+    pub fn get_url(&self) -> Option<String> {
+        self.payload.url.clone()
+    }
+
+    pub fn get_sticker_id(&self) -> Option<u64> {
+        self.payload.sticker_id
+    }
+
+    pub fn get_coordinates(&self) -> Option<Coordinates> {
+        self.payload.coordinates
+    }
+}
+
+/// `Message` is a message sent by the user, or an echo of a message sent by the page.
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Message {
+    mid: Option<String>,
     text: Option<String>,
     quick_reply: Option<QuickReplyPayload>,
+    attachments: Option<Vec<Attachment>>,
+    #[serde(default)]
+    is_echo: bool,
+    app_id: Option<u64>,
+    metadata: Option<String>,
 }
 
 impl Message {
+    /// Returns `true` if this message was sent by the page and echoed back by Facebook.
+    pub fn is_echo(&self) -> bool {
+        self.is_echo
+    }
+
+    /// Returns the id of the app that sent the echoed message, or `None` if it was sent from the page inbox.
+    pub fn get_app_id(&self) -> Option<u64> {
+        self.app_id
+    }
+
+    pub fn get_metadata(&self) -> Option<String> {
+        self.metadata.clone()
+    }
+
+    pub fn get_mid(&self) -> Option<String> {
+        self.mid.clone()
+    }
+
     pub fn get_text(&self) -> String {
         self.text.clone().unwrap_or_default()
     }
@@ -30,46 +145,389 @@ impl Message {
     pub fn get_quick_reply(&self) -> Option<QuickReplyPayload> {
         self.quick_reply.clone()
     }
+
+    pub fn get_attachments(&self) -> Vec<Attachment> {
+        self.attachments.clone().unwrap_or_default()
+    }
+}
+
+/// `Referral` is sent when the user opens the conversation from an m.me link, an ad or a chat plugin.
+///
+/// # Methods
+///
+/// * `get_ref`: Returns the `ref` parameter of the link, it is decoded with `Payload::from_ref`.
+/// * `get_source`: Returns where the user comes from, like `SHORTLINK` or `ADS`.
+/// * `get_type`: Returns the type of the referral, like `OPEN_THREAD`.
+/// * `get_ad_id`: Returns the id of the ad, for the referrals coming from an ad.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Referral {
+    #[serde(rename = "ref")]
+    referral: Option<String>,
+    source: Option<String>,
+    #[serde(rename = "type")]
+    r#type: Option<String>,
+    ad_id: Option<String>,
+}
+
+impl Referral {
+    pub fn get_ref(&self) -> String {
+        self.referral.clone().unwrap_or_default()
+    }
+
+    pub fn get_source(&self) -> Option<String> {
+        self.source.clone()
+    }
+
+    pub fn get_type(&self) -> Option<String> {
+        self.r#type.clone()
+    }
+
+    pub fn get_ad_id(&self) -> Option<String> {
+        self.ad_id.clone()
+    }
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Postback {
+    mid: Option<String>,
     payload: String,
+    referral: Option<Referral>,
 }
 
 impl Postback {
+    /// Returns the referral of a GET STARTED postback sent from an m.me link.
+    pub fn get_referral(&self) -> Option<Referral> {
+        self.referral.clone()
+    }
+
+    pub fn get_mid(&self) -> Option<String> {
+        self.mid.clone()
+    }
+
     pub fn get_payload(&self) -> &String {
         &self.payload
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+/// `Delivery` tells that the messages sent to the user before `watermark` were delivered.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Delivery {
+    #[serde(default)]
+    mids: Vec<String>,
+    #[serde(default)]
+    watermark: u64,
+}
+
+impl Delivery {
+    pub fn get_mids(&self) -> Vec<String> {
+        self.mids.clone()
+    }
+
+    pub fn get_watermark(&self) -> u64 {
+        self.watermark
+    }
+}
+
+/// `Read` tells that the messages sent to the user before `watermark` were read.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Read {
+    #[serde(default)]
+    watermark: u64,
+}
+
+impl Read {
+    pub fn get_watermark(&self) -> u64 {
+        self.watermark
+    }
+}
+
+/// `ReactionAction` tells whether the user added or removed a reaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReactionAction {
+    #[default]
+    React,
+    Unreact,
+}
+
+/// `Reaction` is sent when the user reacts to a message with an emoji, or removes the reaction.
+///
+/// # Methods
+///
+/// * `get_action`: Returns whether the reaction was added or removed.
+/// * `get_emoji`: Returns the emoji of the reaction.
+/// * `get_reaction`: Returns the name of the reaction, like `love`, `smile` or `other`.
+/// * `get_mid`: Returns the id of the message the user reacted to.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Reaction {
+    #[serde(default)]
+    action: ReactionAction,
+    emoji: Option<String>,
+    reaction: Option<String>,
+    #[serde(default)]
+    mid: String,
+}
+
+impl Reaction {
+    pub fn get_action(&self) -> ReactionAction {
+        self.action
+    }
+
+    pub fn get_emoji(&self) -> Option<String> {
+        self.emoji.clone()
+    }
+
+    pub fn get_reaction(&self) -> Option<String> {
+        self.reaction.clone()
+    }
+
+    pub fn get_mid(&self) -> &String {
+        &self.mid
+    }
+}
+
+/// `ThreadControl` holds the apps involved in a change of the owner of the conversation.
+///
+/// # Methods
+///
+/// * `get_previous_owner_app_id`: Returns the app that owned the conversation.
+/// * `get_new_owner_app_id`: Returns the app that owns the conversation now.
+/// * `get_requested_owner_app_id`: Returns the app asking for the control of the conversation.
+/// * `get_metadata`: Returns the data sent by the app that changed the owner.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThreadControl {
+    previous_owner_app_id: Option<Value>,
+    new_owner_app_id: Option<Value>,
+    requested_owner_app_id: Option<Value>,
+    metadata: Option<String>,
+}
+
+fn app_id(value: &Option<Value>) -> Option<String> {
+    match value {
+        Some(Value::String(app_id)) => Some(app_id.clone()),
+        Some(Value::Number(app_id)) => Some(app_id.to_string()),
+        _ => None,
+    }
+}
+
+impl ThreadControl {
+    pub fn get_previous_owner_app_id(&self) -> Option<String> {
+        app_id(&self.previous_owner_app_id)
+    }
+
+    pub fn get_new_owner_app_id(&self) -> Option<String> {
+        app_id(&self.new_owner_app_id)
+    }
+
+    pub fn get_requested_owner_app_id(&self) -> Option<String> {
+        app_id(&self.requested_owner_app_id)
+    }
+
+    pub fn get_metadata(&self) -> Option<String> {
+        self.metadata.clone()
+    }
+}
+
+/// `Handover` is a change of the owner of the conversation, sent by the handover protocol.
+///
+/// # Variants
+///
+/// * `Pass` - Another app passed the control of the conversation to the bot.
+/// * `Take` - The primary receiver took the control of the conversation from the bot.
+/// * `Request` - A secondary receiver asks the bot for the control of the conversation.
+#[derive(Debug, Clone)]
+pub enum Handover {
+    Pass(ThreadControl),
+    Take(ThreadControl),
+    Request(ThreadControl),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct Messaging {
     sender: Sender,
+    #[serde(default)]
+    recipient: Sender,
+    #[serde(default)]
+    timestamp: u64,
     postback: Option<Postback>,
     message: Option<Message>,
+    referral: Option<Referral>,
+    reaction: Option<Reaction>,
+    delivery: Option<Delivery>,
+    read: Option<Read>,
+    pass_thread_control: Option<ThreadControl>,
+    take_thread_control: Option<ThreadControl>,
+    request_thread_control: Option<ThreadControl>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Messaging {
+    pub fn get_sender(&self) -> &String {
+        &self.sender.id
+    }
+
+    /// Returns the user of the conversation: the sender, or the recipient for an echo sent by the page.
+    pub fn get_user(&self) -> &String {
+        match &self.message {
+            Some(message) if message.is_echo() => &self.recipient.id,
+            _ => &self.sender.id,
+        }
+    }
+
+    pub fn get_message(&self) -> Option<Message> {
+        self.message.clone()
+    }
+
+    pub fn get_postback(&self) -> Option<Postback> {
+        self.postback.clone()
+    }
+
+    pub fn get_referral(&self) -> Option<Referral> {
+        self.referral.clone()
+    }
+
+    pub fn get_reaction(&self) -> Option<Reaction> {
+        self.reaction.clone()
+    }
+
+    pub fn get_delivery(&self) -> Option<Delivery> {
+        self.delivery.clone()
+    }
+
+    pub fn get_read(&self) -> Option<Read> {
+        self.read.clone()
+    }
+
+    pub fn get_handover(&self) -> Option<Handover> {
+        match (
+            &self.pass_thread_control,
+            &self.take_thread_control,
+            &self.request_thread_control,
+        ) {
+            (Some(control), _, _) => Some(Handover::Pass(control.clone())),
+            (None, Some(control), _) => Some(Handover::Take(control.clone())),
+            (None, None, Some(control)) => Some(Handover::Request(control.clone())),
+            (None, None, None) => None,
+        }
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns an id identifying the event across redeliveries.
+    ///
+    /// It is the kind of the event followed by the `mid` of the message or of the postback,
+    /// or by the sender and the timestamp of the event when Facebook sends no `mid`.
+    /// Deliveries and reads have no id, handling them twice is harmless.
+    pub fn get_event_id(&self) -> Option<String> {
+        let (kind, mid) = match (&self.message, &self.postback) {
+            (Some(message), _) => ("message", message.get_mid()),
+            (None, Some(postback)) => ("postback", postback.get_mid()),
+            (None, None) if self.referral.is_some() => ("referral", None),
+            (None, None) if self.reaction.is_some() => ("reaction", None),
+            (None, None) => return None,
+        };
+        match (mid, self.timestamp) {
+            (Some(mid), _) => Some(format!("{kind}:{mid}")),
+            (None, 0) => None,
+            (None, timestamp) => Some(format!("{kind}:{}:{timestamp}", self.sender.id)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
 pub struct Entry {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
     messaging: Vec<Messaging>,
+    #[serde(default)]
+    standby: Vec<Messaging>,
 }
 
+/// Facebook may batch several entries, each holding several messaging events
+/// (possibly from different users), into a single webhook delivery.
 #[derive(Debug, Deserialize)]
 pub struct InComingData {
+    #[serde(default)]
+    object: String,
+    #[serde(default)]
     entry: Vec<Entry>,
 }
 
 impl InComingData {
-    pub fn get_sender(&self) -> &String {
-        &self.entry[0].messaging[0].sender.id
+    /// Returns the platform of the delivery: `instagram` for an Instagram account, `page` for a Facebook Page.
+    pub fn get_platform(&self) -> Platform {
+        match self.object.as_str() {
+            "instagram" => Platform::Instagram,
+            _ => Platform::Messenger,
+        }
     }
 
-    pub fn get_message(&self) -> Option<Message> {
-        self.entry[0].messaging[0].message.clone()
+    /// Returns every messaging event of the delivery, in the order they were sent, with the ID of the page receiving it.
+    pub fn get_messaging(&self) -> impl Iterator<Item = (&str, &Messaging)> {
+        self.entry.iter().flat_map(|entry| {
+            let page_id = entry.id.as_str();
+            entry
+                .messaging
+                .iter()
+                .map(move |messaging| (page_id, messaging))
+        })
     }
 
-    pub fn get_postback(&self) -> Option<Postback> {
-        self.entry[0].messaging[0].postback.clone()
+    /// Returns the events received while another app owns the conversation, with the ID of the page receiving them.
+    pub fn get_standby(&self) -> impl Iterator<Item = (&str, &Messaging)> {
+        self.entry.iter().flat_map(|entry| {
+            let page_id = entry.id.as_str();
+            entry
+                .standby
+                .iter()
+                .map(move |messaging| (page_id, messaging))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messaging(json: &str) -> Messaging {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn the_event_id_is_prefixed_with_its_kind() {
+        let message =
+            messaging(r#"{"sender":{"id":"u1"},"timestamp":7,"message":{"mid":"m1","text":"hi"}}"#);
+        assert_eq!(message.get_event_id().as_deref(), Some("message:m1"));
+
+        let referral = messaging(
+            r#"{"sender":{"id":"u1"},"timestamp":7,"referral":{"ref":"Promo","source":"SHORTLINK","type":"OPEN_THREAD"}}"#,
+        );
+        let reaction = messaging(
+            r#"{"sender":{"id":"u1"},"timestamp":7,"reaction":{"mid":"m1","action":"react","reaction":"love","emoji":"x"}}"#,
+        );
+        assert_eq!(referral.get_event_id().as_deref(), Some("referral:u1:7"));
+        assert_eq!(reaction.get_event_id().as_deref(), Some("reaction:u1:7"));
+
+        let read = messaging(r#"{"sender":{"id":"u1"},"timestamp":7,"read":{"watermark":7}}"#);
+        assert_eq!(read.get_event_id(), None);
+    }
+
+    #[test]
+    fn partial_deliveries_and_reads_are_parsed() {
+        let delivery = messaging(r#"{"sender":{"id":"u1"},"timestamp":7,"delivery":{}}"#);
+        let read = messaging(r#"{"sender":{"id":"u1"},"timestamp":7,"read":{}}"#);
+        assert_eq!(delivery.get_delivery().unwrap().get_watermark(), 0);
+        assert_eq!(read.get_read().unwrap().get_watermark(), 0);
+    }
+
+    #[test]
+    fn a_reaction_without_mid_is_parsed() {
+        let reaction =
+            messaging(r#"{"sender":{"id":"u1"},"timestamp":7,"reaction":{"action":"unreact"}}"#);
+        let reaction = reaction.get_reaction().unwrap();
+        assert_eq!(reaction.get_action(), ReactionAction::Unreact);
+        assert_eq!(reaction.get_mid(), "");
     }
 }
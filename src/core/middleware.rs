@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use super::{
+    action::{Action, ActionResult},
+    request::Req,
+    response::Res,
+};
+
+/// The `Middleware` trait wraps the execution of the actions.
+///
+/// A middleware receives the `Res` and the `Req` before the action. It can change the `Req`, answer the user and stop there,
+/// or call `next.run` to go on with the next middleware and then the action, and run code once they are done.
+/// The error of the action goes back through the middlewares, which can handle it or return it to the error handler of the `App`.
+///
+/// The middlewares registered with `App::middleware` wrap every action, the ones registered with `App::action_middleware` only wrap one action.
+/// The global middlewares run first, in the order they were registered.
+///
+/// # Methods
+///
+/// * `handle`: This method is called instead of the action, `next` runs the rest of the chain.
+///
+/// # Examples
+///
+/// Showing the typing indicator and logging every action:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// struct Typing;
+///
+/// #[russenger::async_trait]
+/// impl Middleware for Typing {
+///     async fn handle(&self, res: Res, req: Req, next: Next<'_>) -> ActionResult {
+///         res.send(SenderActionModel::new(&req.user, TypingOn)).await;
+///         let (user, path) = (req.user.clone(), next.path());
+///         let result = next.run(res, req).await;
+///         println!("{path} answered {user}");
+///         result
+///     }
+/// }
+///
+/// struct AdminOnly;
+///
+/// #[russenger::async_trait]
+/// impl Middleware for AdminOnly {
+///     async fn handle(&self, res: Res, req: Req, next: Next<'_>) -> ActionResult {
+///         if req.user != "admin_id" {
+///             res.send(TextModel::new(&req.user, "Access denied")).await;
+///             return Ok(());
+///         }
+///         next.run(res, req).await
+///     }
+/// }
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {}
+///
+/// #[action]
+/// async fn Admin(res: Res, req: Req) {}
+///
+/// #[russenger::main]
+/// async fn main() {
+///     russenger::App::new()
+///         .action(Main)
+///         .action(Admin)
+///         .middleware(Typing)
+///         .action_middleware(Admin, AdminOnly)
+///         .launch()
+///         .await;
+/// }
+/// ```
+#[async_trait::async_trait]
+pub trait Middleware: Send + Sync {
+    async fn handle(&self, res: Res, req: Req, next: Next<'_>) -> ActionResult;
+}
+
+/// `Next` is the rest of the middleware chain, ending with the action.
+///
+/// # Methods
+///
+/// * `run`: Runs the next middleware, or the action once every middleware ran.
+/// * `path`: Returns the path of the wrapped action.
+pub struct Next<'a> {
+    action: &'a Arc<dyn Action>,
+    middlewares: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    pub fn new(action: &'a Arc<dyn Action>, middlewares: &'a [Arc<dyn Middleware>]) -> Self {
+        Self {
+            action,
+            middlewares,
+        }
+    }
+
+    pub async fn run(self, res: Res, req: Req) -> ActionResult {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => {
+                let next = Next::new(self.action, middlewares);
+                middleware.handle(res, req, next).await
+            }
+            None => self.action.try_execute(res, req).await,
+        }
+    }
+
+    pub fn path(&self) -> String {
+        self.action.path()
+    }
+}
@@ -4,13 +4,25 @@
 //!
 //! # Submodules
 //!
-//! * `action`: This module contains the `Action` trait and the `ACTION_REGISTRY`.
+//! * `action`: This module contains the `Action` trait and the `Hook` events.
+//! * `app`: This module contains the `App` builder that holds the actions, the state and the configuration of a bot.
 //! * `app_state`: This module contains the `AppState` struct that represents the state of the application.
+//! * `command`: This module contains the `Command` struct that routes the text messages matching a pattern to an action.
+//! * `conversation`: This module contains the `Conversations` struct that gives the answers of the users to the actions waiting in `req.ask`.
+//! * `deduplicator`: This module contains the `Deduplicator` struct that skips the webhook events already processed.
 //! * `request`: This module contains the `Req` struct that represents a request from a user.
 //! * `response`: This module contains the `Res` struct that represents a response that can be sent to a user.
+//! * `session`: This module contains the `Session` struct that stores typed values for a user.
 //! * `services`: This module contains various services that the application can use.
-//! * `incoming_data`: This module contains the functionality to handle incoming data.
+//! * `middleware`: This module contains the `Middleware` trait that wraps the execution of the actions.
+//! * `error_handler`: This module contains the `ErrorHandler` trait that handles the errors returned by the actions.
+//! * `form`: This module contains the `Form` action that asks the user a list of `Field` and validates the answers.
+//! * `history`: This module contains the history of the actions visited by a user, and the `Back` action.
+//! * `incoming_data`: This module contains the functionality to handle incoming data, including the `Attachment` sent by a user.
+//! * `page_tokens`: This module contains the `PageTokens` struct that caches the access tokens of the pages.
 //! * `request_handler`: This module contains the functionality to handle requests.
+//! * `thread_owners`: This module contains the `ThreadOwners` struct that tracks the conversations handed over to another app.
+//! * `worker_pool`: This module contains the `WorkerPool` struct that runs the actions in the background.
 //!
 //! # Examples
 //!
@@ -26,10 +38,21 @@
 //! ```
 
 pub mod action;
+pub mod app;
 pub mod app_state;
+pub mod command;
+pub mod conversation;
+pub mod deduplicator;
+pub mod error_handler;
+pub mod form;
+pub mod history;
+pub mod incoming_data;
+pub mod middleware;
+pub mod page_tokens;
 pub mod request;
 pub mod request_handler;
 pub mod response;
 pub mod services;
-
-mod incoming_data;
+pub mod session;
+pub mod thread_owners;
+pub mod worker_pool;
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+use crate::query::Query;
+
+/// How long a page token read from the database is kept in memory.
+pub const PAGE_TOKEN_TTL: Duration = Duration::from_secs(300);
+
+/// The token of a page, or `None` if the page is not in the table, with the time it was read.
+type CachedToken = (Option<String>, Instant);
+
+/// The `PageTokens` struct caches the access tokens of the pages read from the `russenger_page` table.
+///
+/// Each event is answered with the token of its page, the cache avoids a query per event.
+/// A page missing from the table is cached too, so a token saved with `Query::set_page_token` is used after at most `ttl`.
+///
+/// # Methods
+///
+/// * `new`: This method creates a new `PageTokens` reading the tokens with the `Query` and keeping them for `ttl`.
+/// * `get`: This method returns the token of a page, or `None` if the page is not in the table.
+#[derive(Clone)]
+pub struct PageTokens {
+    tokens: Arc<Mutex<HashMap<String, CachedToken>>>,
+    ttl: Duration,
+    query: Query,
+}
+
+impl PageTokens {
+    pub fn new(query: Query, ttl: Duration) -> Self {
+        Self {
+            tokens: Arc::default(),
+            ttl,
+            query,
+        }
+    }
+
+    pub async fn get(&self, page_id: &str) -> Option<String> {
+        if let Some((token, time)) = self.tokens.lock().await.get(page_id) {
+            if time.elapsed() < self.ttl {
+                return token.clone();
+            }
+        }
+        let token = self.query.get_page_token(page_id).await;
+        let entry = (token.clone(), Instant::now());
+        self.tokens.lock().await.insert(page_id.to_owned(), entry);
+        token
+    }
+}
@@ -1,5 +1,16 @@
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+use actix_web::rt::time::timeout;
+
+use super::action::ActionError;
+use super::app::States;
+use super::conversation::{Conversations, Interrupted};
+use super::incoming_data::{Attachment, Delivery, Handover, Message, Reaction, Read, Referral};
+use super::response::Res;
+use super::session::Session;
 use crate::query::Query;
-use crate::response_models::data::Data;
+use crate::response_models::{data::Data, text::TextModel, Platform};
 
 /// The `Req` struct represents a request from a user.
 ///
@@ -7,7 +18,12 @@ use crate::response_models::data::Data;
 /// * `user`: A `String` that represents the user who made the request.
 /// * `query`: A `Query` that represents the query made by the user.
 /// * `data`: A `Data` that represents the data associated with the request.
+/// * `attachments`: A `Vec<Attachment>` that holds the files, locations or stickers sent by the user.
 /// * `host`: A `String` that represents the host from which the request was made.
+/// * `platform`: The `Platform`, Messenger or Instagram, the request comes from.
+/// * `page_id`: The ID of the page, or of the Instagram account, the user talks to.
+/// * `referral`: A `Referral` when the user comes from an m.me link or an ad.
+/// * `delivery`, `read`, `echo`, `reaction`, `handover`: The event handled by a `Hook` action, `None` for the other actions.
 #[derive(Clone)]
 pub struct Req {
     pub user: String,
@@ -72,6 +88,28 @@ pub struct Req {
     /// ```
     pub data: Data,
 
+    /// The `attachments` field holds the images, audios, videos, files, locations and stickers sent by the user.
+    ///
+    /// It is empty when the user sent a text message or clicked on a button, use `is_attachment` to tell them apart.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {
+    ///     if req.is_attachment() {
+    ///         let count = req.attachments.len();
+    ///         res.send(TextModel::new(&req.user, &format!("{count} file(s) received"))).await;
+    ///     } else {
+    ///         let text: String = req.data.get_value();
+    ///         res.send(TextModel::new(&req.user, &text)).await;
+    ///     }
+    /// }
+    /// ```
+    pub attachments: Vec<Attachment>,
+
     /// The `host` field represents the host name or IP address of the server that the request is being sent to.
     ///
     /// This field is used to specify the server that the request should be sent to. It is a `String` that contains the host name or IP address of the server.
@@ -91,6 +129,105 @@ pub struct Req {
     /// }
     /// ```
     pub host: String,
+
+    /// The `platform` field tells whether the request comes from Messenger or Instagram.
+    ///
+    /// The `Res` given to the action sends its responses to the same platform.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {
+    ///     let text = match req.platform {
+    ///         Platform::Messenger => "Hello from Messenger",
+    ///         Platform::Instagram => "Hello from Instagram",
+    ///     };
+    ///     res.send(TextModel::new(&req.user, text)).await;
+    /// }
+    /// ```
+    pub platform: Platform,
+
+    /// The `page_id` field holds the ID of the page, or of the Instagram account, receiving the messages of the user.
+    ///
+    /// One deployment can serve several pages, the `Res` given to the action answers with the access token of this page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {
+    ///     let text = match req.page_id.as_str() {
+    ///         "111111111111111" => "Welcome to the shop",
+    ///         _ => "Welcome",
+    ///     };
+    ///     res.send(TextModel::new(&req.user, text)).await;
+    /// }
+    /// ```
+    pub page_id: String,
+
+    /// The `referral` field holds the m.me link or the ad the user comes from.
+    ///
+    /// The `ref` of the referral is decoded with `Payload::from_ref`, and the request is routed to the action of the payload with its data.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Promo(res: Res, req: Req) {
+    ///     if let Some(referral) = &req.referral {
+    ///         let source = referral.get_source().unwrap_or_default();
+    ///         res.send(TextModel::new(&req.user, &format!("Welcome from {source}"))).await;
+    ///     }
+    /// }
+    /// ```
+    pub referral: Option<Referral>,
+
+    /// The `delivery` field holds the delivery receipt handled by the `Hook::Delivery` action.
+    pub delivery: Option<Delivery>,
+
+    /// The `read` field holds the read receipt handled by the `Hook::Read` action.
+    pub read: Option<Read>,
+
+    /// The `echo` field holds the message sent by the page and handled by the `Hook::Echo` action.
+    pub echo: Option<Message>,
+
+    /// The `reaction` field holds the reaction handled by the `Hook::Reaction` action, with the id of the message the user reacted to.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn OnReaction(res: Res, req: Req) {
+    ///     if let Some(reaction) = &req.reaction {
+    ///         if reaction.get_action() == ReactionAction::React {
+    ///             let emoji = reaction.get_emoji().unwrap_or_default();
+    ///             res.send(TextModel::new(&req.user, &format!("Thanks for the {emoji}"))).await;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// russenger_app!(Main; Hook::Reaction => OnReaction);
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {}
+    /// ```
+    pub reaction: Option<Reaction>,
+
+    /// The `handover` field holds the change of the owner of the conversation handled by the `Hook::Handover` action.
+    pub handover: Option<Handover>,
+
+    pub(crate) states: Arc<States>,
+
+    pub(crate) conversations: Conversations,
 }
 
 impl Req {
@@ -111,7 +248,96 @@ impl Req {
             user: user.to_owned(),
             query,
             data,
+            attachments: Vec::new(),
             host: host.to_owned(),
+            platform: Platform::default(),
+            page_id: String::new(),
+            referral: None,
+            delivery: None,
+            read: None,
+            echo: None,
+            reaction: None,
+            handover: None,
+            states: Arc::default(),
+            conversations: Conversations::default(),
+        }
+    }
+
+    /// Returns the value of type `T` shared with `App::state`, or `None` if the `App` has none.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// struct Shop {
+    ///     name: String,
+    /// }
+    ///
+    /// #[action]
+    /// async fn Main(res: Res, req: Req) {
+    ///     if let Some(shop) = req.state::<Shop>() {
+    ///         res.send(TextModel::new(&req.user, &format!("Welcome to {}", shop.name))).await;
+    ///     }
+    /// }
+    /// ```
+    pub fn state<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        let state: &(dyn Any + Send + Sync) = self.states.get(&TypeId::of::<T>())?.as_ref();
+        state.downcast_ref()
+    }
+
+    /// Returns the `Session` of the user, to keep typed values between the messages.
+    pub fn session(&self) -> Session<'_> {
+        Session::new(&self.user, &self.query)
+    }
+
+    /// Sends a question to the user and waits for the answer, so a dialogue can be written in a single action.
+    ///
+    /// The next text message of the user, or the text of the quick reply they clicked, is the answer.
+    /// If the user does not answer before the timeout set with `App::ask_timeout`, 5 minutes by default,
+    /// the action of the user is set back to `Main` and an error is returned.
+    /// If the user sends a command, clicks a button or sends attachments instead, `Interrupted` is returned
+    /// and the event is routed as usual once the action returned.
+    ///
+    /// The worker running the action is released while it waits, so the users who do not answer do not hold the workers of the `App`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// struct Greet;
+    ///
+    /// #[russenger::async_trait]
+    /// impl FallibleAction for Greet {
+    ///     async fn try_execute(&self, res: Res, req: Req) -> ActionResult {
+    ///         let name = req.ask(&res, "What's your name?").await?;
+    ///         let city = req.ask(&res, "Where do you live?").await?;
+    ///         res.send(TextModel::new(&req.user, &format!("Hello {name} from {city}!"))).await;
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn path(&self) -> String {
+    ///         "Greet".to_owned()
+    ///     }
+    /// }
+    /// ```
+    pub async fn ask(&self, res: &Res, question: &str) -> Result<String, ActionError> {
+        let answer = self.conversations.wait(&self.user);
+        res.send(TextModel::new(&self.user, question)).await;
+        match timeout(self.conversations.get_timeout(), answer).await {
+            Ok(Ok(answer)) => Ok(answer),
+            Ok(Err(_)) => Err(Interrupted.into()),
+            Err(_) => {
+                self.conversations.cancel(&self.user);
+                self.query.reset_action(&self.user).await;
+                Err(format!("{} did not answer {question:?} in time", self.user).into())
+            }
         }
     }
+
+    /// Returns `true` if the user sent attachments instead of a text message.
+    pub fn is_attachment(&self) -> bool {
+        !self.attachments.is_empty()
+    }
 }
@@ -1,5 +1,9 @@
 use actix_web::HttpResponse;
+use hmac::{Hmac, Mac};
 use serde::Deserialize;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug, Deserialize)]
 pub struct WebQuery {
@@ -29,3 +33,97 @@ impl WebQuery {
         }
     }
 }
+
+/// Checks the `X-Hub-Signature-256` header of a webhook delivery.
+///
+/// Facebook signs every POST with an HMAC-SHA256 of the raw body, keyed with the app secret,
+/// and sends it as `sha256=<hex digest>`. The digest is recomputed with the `APP_SECRET` env
+/// variable and compared in constant time.
+///
+/// Set `SKIP_SIGNATURE_VERIFICATION=true` to disable the check during local development.
+///
+/// # Arguments
+///
+/// * `signature`: The value of the `X-Hub-Signature-256` header, if present.
+/// * `body`: The raw body of the request.
+///
+/// # Returns
+///
+/// * `bool`: Whether the delivery is authentic.
+pub fn verify_signature(signature: Option<&str>, body: &[u8]) -> bool {
+    let skip = std::env::var("SKIP_SIGNATURE_VERIFICATION").is_ok_and(|skip| skip.eq("true"));
+    let app_secret = std::env::var("APP_SECRET").ok();
+    verify_with(signature, body, app_secret.as_deref(), skip)
+}
+
+/// Checks the signature of a webhook delivery against `app_secret`, `verify_signature` reads it from the env.
+fn verify_with(signature: Option<&str>, body: &[u8], app_secret: Option<&str>, skip: bool) -> bool {
+    if skip {
+        return true;
+    }
+    let Some(app_secret) = app_secret else {
+        return false;
+    };
+    let signature = signature
+        .and_then(|signature| signature.strip_prefix("sha256="))
+        .and_then(|digest| hex::decode(digest).ok());
+    match (signature, HmacSha256::new_from_slice(app_secret.as_bytes())) {
+        (Some(signature), Ok(mut mac)) => {
+            mac.update(body);
+            mac.verify_slice(&signature).is_ok()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"object":"page","entry":[]}"#;
+
+    fn sign(body: &[u8], app_secret: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(app_secret.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn a_signed_delivery_is_authentic() {
+        let signature = sign(BODY, "secret");
+        assert!(verify_with(Some(&signature), BODY, Some("secret"), false));
+    }
+
+    #[test]
+    fn a_tampered_body_is_rejected() {
+        let signature = sign(BODY, "secret");
+        let tampered = br#"{"object":"page","entry":[{}]}"#;
+        assert!(!verify_with(
+            Some(&signature),
+            tampered,
+            Some("secret"),
+            false
+        ));
+        assert!(!verify_with(Some(&signature), BODY, Some("other"), false));
+    }
+
+    #[test]
+    fn the_signature_header_is_required() {
+        assert!(!verify_with(None, BODY, Some("secret"), false));
+        let digest = sign(BODY, "secret").replace("sha256=", "");
+        assert!(!verify_with(Some(&digest), BODY, Some("secret"), false));
+        assert!(!verify_with(Some("sha256=zz"), BODY, Some("secret"), false));
+    }
+
+    #[test]
+    fn nothing_is_authentic_without_an_app_secret() {
+        let signature = sign(BODY, "secret");
+        assert!(!verify_with(Some(&signature), BODY, None, false));
+    }
+
+    #[test]
+    fn the_check_can_be_skipped() {
+        assert!(verify_with(None, BODY, None, true));
+        assert!(verify_with(Some("sha256=00"), BODY, Some("secret"), true));
+    }
+}
@@ -1,16 +1,39 @@
 use std::env;
 
-use crate::response_models::ResponseModel;
+use super::action::ActionError;
+use crate::response_models::{Platform, ResponseModel};
 
+/// The result of `res.send`.
+///
+/// * `Okey`: The response was sent, Facebook may still have refused it, check the status of the response.
+/// * `Error`: The response could not be sent.
+/// * `Rejected`: The response was not sent because it exceeds the limits of the platform of the conversation.
+///
+/// More variants may be added, so a `match` on a `SendResult` needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SendResult {
     Okey(reqwest::Response),
     Error(reqwest::Error),
+    Rejected(String),
+}
+
+impl SendResult {
+    /// Turns the `SendResult` into a `Result`, so a fallible action can use `?` on `res.send`.
+    ///
+    /// The responses sent but refused by Facebook are `Okey`, check the status of the response for them.
+    pub fn into_result(self) -> Result<reqwest::Response, ActionError> {
+        match self {
+            SendResult::Okey(response) => Ok(response),
+            SendResult::Error(error) => Err(error.into()),
+            SendResult::Rejected(reason) => Err(reason.into()),
+        }
+    }
 }
 
 /// The `Res` struct represents a response that can be sent to a user.
 ///
-/// It does not contain any fields.
+/// It holds the `Platform`, Messenger or Instagram, the responses are sent to, and the access token of the page the user talks to.
 ///
 /// # Examples
 ///
@@ -18,9 +41,10 @@ pub enum SendResult {
 ///
 /// ```rust
 /// use russenger::response_models::text::TextModel;
+/// use russenger::response_models::Platform;
 /// use russenger::core::response::Res;
 ///
-/// let res = Res;
+/// let res = Res::new(Platform::Messenger);
 /// let response_model = TextModel::new("sender_id", "Hello, user1!");
 ///
 /// let send_result = res.send(response_model);
@@ -28,12 +52,38 @@ pub enum SendResult {
 ///
 /// # Methods
 ///
+/// * `new`: Creates a `Res` sending the responses to the given platform, with the token of the env variables.
+/// * `for_page`: Creates a `Res` sending the responses with the access token of a page.
 /// * `send`: Sends a response to a user. It takes a `ResponseModel` as an argument and returns a `SendResult`.
-pub struct Res;
+#[derive(Debug, Clone, Default)]
+pub struct Res {
+    platform: Platform,
+    access_token: Option<String>,
+}
 
 impl Res {
+    pub fn new(platform: Platform) -> Self {
+        Self {
+            platform,
+            access_token: None,
+        }
+    }
+
+    /// Creates a `Res` answering with the access token of a page, when one deployment serves several pages.
+    ///
+    /// The token is read from the `russenger_page` table with `Query::get_page_token`, and cached for a few minutes by `PageTokens`.
+    pub fn for_page(platform: Platform, access_token: String) -> Self {
+        Self {
+            platform,
+            access_token: Some(access_token),
+        }
+    }
+
     /// Sends a response to a user.
     ///
+    /// The access token of the page is used when the `Res` was created with `for_page`.
+    /// Otherwise the Instagram responses use the `INSTAGRAM_ACCESS_TOKEN` env variable when it is set, the `PAGE_ACCESS_TOKEN` otherwise.
+    ///
     /// # Arguments
     ///
     /// * `response_model`: A `ResponseModel` that represents the response to be sent.
@@ -44,15 +94,29 @@ impl Res {
     ///
     /// # Errors
     ///
-    /// Returns `SendResult::Error` if the send operation fails.
+    /// Returns `SendResult::Rejected` if the response exceeds the limits of the platform, and `SendResult::Error` if the send operation fails.
     pub async fn send<T: ResponseModel>(&self, response_model: T) -> SendResult {
+        if let Err(reason) = response_model.check(self.platform) {
+            return SendResult::Rejected(reason);
+        }
         let version = env::var("FACEBOOK_API_VERSION").unwrap_or("v15.0".into());
-        let page_access_token =
-            env::var("PAGE_ACCESS_TOKEN").expect("env variable `PAGE_ACCESS_TOKEN` should be set");
-        let url_api = format!(
+        let page_access_token = match (&self.access_token, self.platform) {
+            (Some(access_token), _) => Ok(access_token.clone()),
+            (None, Platform::Instagram) => {
+                env::var("INSTAGRAM_ACCESS_TOKEN").or(env::var("PAGE_ACCESS_TOKEN"))
+            }
+            (None, Platform::Messenger) => env::var("PAGE_ACCESS_TOKEN"),
+        }
+        .expect("env variable `PAGE_ACCESS_TOKEN` should be set");
+        let mut url_api = format!(
             "https://graph.facebook.com/{version}/me/{endpoint}?access_token={page_access_token}",
             endpoint = response_model.get_endpoint()
         );
+        if self.platform == Platform::Instagram
+            && response_model.get_endpoint() == "messenger_profile"
+        {
+            url_api.push_str("&platform=instagram");
+        }
         match reqwest::Client::new()
             .post(url_api)
             .json(&response_model)
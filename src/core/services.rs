@@ -1,19 +1,30 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::pin::pin;
 use std::str::FromStr;
+use std::sync::Arc;
 
-use actix_web::{dev, get, post, web, HttpResponse};
+use actix_web::{dev, get, post, rt, web, HttpRequest, HttpResponse};
+use futures_util::future::select;
+use futures_util::FutureExt;
+use tokio::sync::Notify;
 
 use super::{
-    action::{ACTION_LOCK, ACTION_REGISTRY},
+    action::{Action, ActionError, Hook, Job, Mailbox},
     app_state::AppState,
-    incoming_data::InComingData,
+    conversation::{suspendable, Interrupted},
+    history::{self, Back},
+    incoming_data::{Attachment, Handover, InComingData, Messaging, Referral},
+    middleware::{Middleware, Next},
     request::Req,
-    request_handler::WebQuery,
-    response::Res as res,
+    request_handler::{verify_signature, WebQuery},
+    response::Res,
 };
 
-use crate::{
-    query::Query,
-    response_models::{data::Data, payload::Payload},
+use crate::response_models::{
+    data::Data,
+    payload::{decode_ref, Payload},
+    Platform,
 };
 
 #[get("/webhook")]
@@ -21,57 +32,418 @@ pub async fn webhook_verify(web_query: web::Query<WebQuery>) -> HttpResponse {
     web_query.get_hub_challenge()
 }
 
+/// Clones the action out of the `App`, so the state is not borrowed while the action runs.
+fn find_action(path: &str, app_state: &AppState) -> Option<Arc<dyn Action>> {
+    app_state.actions.get(path).cloned()
+}
+
+/// Runs the action wrapped by the global middlewares, then by its own ones.
+async fn execute_action(action: Arc<dyn Action>, res: Res, req: Req, app_state: &AppState) {
+    let mut middlewares: Vec<Arc<dyn Middleware>> = app_state.middlewares.to_vec();
+    if let Some(action_middlewares) = app_state.action_middlewares.get(&action.path()) {
+        middlewares.extend(action_middlewares.iter().cloned());
+    }
+    let next = Next::new(&action, &middlewares);
+    let result = AssertUnwindSafe(next.run(res.clone(), req.clone()))
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|panic| Err(panic_error(panic)));
+    if let Err(error) = result {
+        handle_error(&action.path(), error, res, req, app_state).await;
+    }
+}
+
+/// Turns the panic of an action into an error, so it is handled like the errors returned by the actions.
+fn panic_error(panic: Box<dyn Any + Send>) -> ActionError {
+    let message = match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_owned(),
+        },
+    };
+    format!("the action panicked: {message}").into()
+}
+
+/// Logs the error of an action, then gives it to the error handler of the `App`.
+async fn handle_error(path: &str, error: ActionError, res: Res, req: Req, app_state: &AppState) {
+    if error.is::<Interrupted>() {
+        return;
+    }
+    eprintln!(
+        "the action {path:?} failed for the user {}: {error}",
+        req.user
+    );
+    if let Some(error_handler) = app_state.error_handler.clone() {
+        error_handler.handle(res, req, error).await;
+    }
+}
+
+/// Runs the action of a payload or of a referral, and records it in the history of the user.
+///
+/// The text messages and the attachments run the stored action of the user without being recorded.
+async fn navigate(path: &str, res: Res, req: Req, app_state: &AppState) {
+    if path != Back.path() && find_action(path, app_state).is_some() {
+        history::record(&req, path).await;
+    }
+    execute(path, res, req, app_state).await;
+}
+
+/// Runs the action of the path, or runs the `not_found` action of the `App` if there is no such action.
+///
+/// The `Back` path runs the previous action of the history again.
+async fn execute(path: &str, res: Res, mut req: Req, app_state: &AppState) {
+    let mut path = path.to_owned();
+    if path == Back.path() {
+        let visit = history::back(&req).await;
+        let payload = Payload::default();
+        path = visit
+            .as_ref()
+            .map_or(payload.get_path(), |visit| visit.path.clone());
+        req.data = visit.map_or(payload.get_data(), |visit| visit.data);
+    }
+    match find_action(&path, app_state) {
+        Some(action) => execute_action(action, res, req, app_state).await,
+        None => {
+            eprintln!("no action found for the path {path:?}");
+            if let Some(action) = app_state.not_found.clone() {
+                execute_action(action, res, req, app_state).await;
+            }
+        }
+    }
+}
+
+async fn run_hook(hook: Hook, res: Res, req: Req, app_state: &AppState) {
+    let action = app_state.hooks.get(&hook).cloned();
+    if let Some(action) = action {
+        let result = AssertUnwindSafe(action.try_execute(res.clone(), req.clone()))
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|panic| Err(panic_error(panic)));
+        if let Err(error) = result {
+            handle_error(&action.path(), error, res, req, app_state).await;
+        }
+    }
+}
+
 pub enum Executable<'a> {
-    Payload(&'a str, &'a str, &'a str, Query),
-    TextMessage(&'a str, &'a str, &'a str, Query),
+    Payload(Req, &'a str),
+    TextMessage(Req, &'a str),
+    Attachments(Req, Vec<Attachment>),
+    Referral(Req, Referral),
 }
 
-async fn run(executable: Executable<'_>) {
+async fn run(res: Res, executable: Executable<'_>, app_state: &AppState) {
     match executable {
-        Executable::Payload(user, payload, host, query) => {
-            let payload = Payload::from_str(payload).unwrap_or_default();
-            {
-                let data = payload.get_data();
-                let req = Req::new(user, query, data, host);
-                if let Some(action) = ACTION_REGISTRY.lock().await.get(&payload.get_path()) {
-                    action.execute(res, req).await;
-                }
+        Executable::Payload(mut req, raw) => match Payload::from_str(raw) {
+            Ok(_) if !Payload::is_authentic(raw) => {
+                eprintln!("rejected payload {raw:?}, its signature is missing or wrong");
+                let action = app_state.rejected_payload.clone();
+                run_fallback(action, raw, res, req, app_state).await;
+            }
+            Ok(payload) => {
+                req.data = payload.get_data().load(&req.query).await;
+                navigate(&payload.get_path(), res, req, app_state).await;
+            }
+            Err(_) => {
+                eprintln!("malformed payload {raw:?}");
+                let action = app_state.invalid_payload.clone();
+                run_fallback(action, raw, res, req, app_state).await;
+            }
+        },
+        Executable::TextMessage(mut req, text_message) => {
+            let command = app_state
+                .commands
+                .iter()
+                .find(|command| command.is_match(text_message));
+            let action_path = match command {
+                Some(command) => Some(command.get_path()),
+                None => req.query.get_action(&req.user).await,
+            };
+            req.data = Data::new(text_message, None);
+            execute(
+                &action_path.unwrap_or("Main".to_string()),
+                res,
+                req,
+                app_state,
+            )
+            .await;
+        }
+        Executable::Referral(mut req, referral) => {
+            let raw = decode_ref(&referral.get_ref())
+                .filter(|raw| Payload::from_str(raw).is_ok())
+                .unwrap_or_else(|| referral.get_ref());
+            if !Payload::is_authentic(&raw) {
+                eprintln!("rejected referral {raw:?}, its signature is missing or wrong");
+                let action = app_state.rejected_payload.clone();
+                return run_fallback(action, &raw, res, req, app_state).await;
+            }
+            let payload = Payload::from_ref(&referral.get_ref());
+            req.data = payload.get_data().load(&req.query).await;
+            req.referral = Some(referral);
+            navigate(&payload.get_path(), res, req, app_state).await;
+        }
+        Executable::Attachments(mut req, attachments) => {
+            let action_path = req.query.get_action(&req.user).await;
+            req.attachments = attachments;
+            execute(
+                &action_path.unwrap_or("Main".to_string()),
+                res,
+                req,
+                app_state,
+            )
+            .await;
+        }
+    }
+}
+
+/// Runs `fallback` with the raw payload in `req.data`, or the `Main` action if there is no `fallback`.
+async fn run_fallback(
+    fallback: Option<Arc<dyn Action>>,
+    raw: &str,
+    res: Res,
+    mut req: Req,
+    app_state: &AppState,
+) {
+    match fallback {
+        Some(action) => {
+            req.data = Data::new(raw, None);
+            execute_action(action, res, req, app_state).await;
+        }
+        None => {
+            let payload = Payload::default();
+            req.data = payload.get_data();
+            execute(&payload.get_path(), res, req, app_state).await;
+        }
+    }
+}
+
+/// Creates the `Res` answering with the token of the page, or with the env variables if the page is not in `russenger_page`.
+async fn page_res(page_id: &str, platform: Platform, app_state: &AppState) -> Res {
+    match app_state.page_tokens.get(page_id).await {
+        Some(access_token) => Res::for_page(platform, access_token),
+        None => Res::new(platform),
+    }
+}
+
+async fn dispatch(
+    messaging: Messaging,
+    page_id: String,
+    host: String,
+    platform: Platform,
+    app_state: AppState,
+) {
+    let user = messaging.get_user();
+    let res = page_res(&page_id, platform, &app_state).await;
+    let mut req = Req::new(user, app_state.query.clone(), Data::default(), &host);
+    req.states = app_state.states.clone();
+    req.conversations = app_state.conversations.clone();
+    req.platform = platform;
+    req.page_id = page_id;
+    if let Some(delivery) = messaging.get_delivery() {
+        req.delivery = Some(delivery);
+        run_hook(Hook::Delivery, res, req, &app_state).await;
+    } else if let Some(read) = messaging.get_read() {
+        req.read = Some(read);
+        run_hook(Hook::Read, res, req, &app_state).await;
+    } else if let Some(reaction) = messaging.get_reaction() {
+        req.reaction = Some(reaction);
+        run_hook(Hook::Reaction, res, req, &app_state).await;
+    } else if let Some(handover) = messaging.get_handover() {
+        match handover {
+            Handover::Pass(_) => app_state.thread_owners.take_back(user).await,
+            Handover::Take(_) => app_state.thread_owners.hand_over(user).await,
+            Handover::Request(_) => {}
+        }
+        req.handover = Some(handover);
+        run_hook(Hook::Handover, res, req, &app_state).await;
+    } else if let Some(message) = messaging.get_message().filter(|message| message.is_echo()) {
+        req.data = Data::new(message.get_text(), None);
+        req.attachments = message.get_attachments();
+        req.echo = Some(message);
+        run_hook(Hook::Echo, res, req, &app_state).await;
+    } else {
+        if app_state.thread_owners.is_handed_over(user).await {
+            app_state.thread_owners.take_back(user).await;
+        }
+        route(&messaging, res, req, &app_state).await;
+    }
+}
+
+/// Routes a message, a postback or a referral of the user to an action.
+async fn route(messaging: &Messaging, res: Res, req: Req, app_state: &AppState) {
+    req.query.create(&req.user, &req.page_id).await;
+    if let Some(message) = messaging.get_message() {
+        if let Some(quick_reply) = message.get_quick_reply() {
+            let payload = quick_reply.get_payload();
+            run(res, Executable::Payload(req, payload), app_state).await;
+        } else if !message.get_attachments().is_empty() {
+            let attachments = message.get_attachments();
+            run(res, Executable::Attachments(req, attachments), app_state).await;
+        } else {
+            let text = message.get_text();
+            run(res, Executable::TextMessage(req, &text), app_state).await;
+        }
+    } else if let Some(postback) = messaging.get_postback() {
+        let referral = postback
+            .get_referral()
+            .filter(|referral| leads_to_action(referral, app_state));
+        match referral {
+            Some(referral) => run(res, Executable::Referral(req, referral), app_state).await,
+            None => {
+                let payload = postback.get_payload();
+                run(res, Executable::Payload(req, payload), app_state).await;
             }
         }
-        Executable::TextMessage(user, text_message, host, query) => {
-            let action_path = query.get_action(user).await.unwrap_or("Main".to_string());
-            let req = Req::new(user, query, Data::new(text_message, None), host);
-            if let Some(action) = ACTION_REGISTRY.lock().await.get(&action_path) {
-                action.execute(res, req).await;
+    } else if let Some(referral) = messaging.get_referral() {
+        run(res, Executable::Referral(req, referral), app_state).await;
+    }
+}
+
+/// Returns `true` if the `ref` of a referral is the path of an action, the postback carrying an empty or unknown `ref` runs its own payload instead.
+fn leads_to_action(referral: &Referral, app_state: &AppState) -> bool {
+    let referral = referral.get_ref();
+    !referral.is_empty()
+        && find_action(&Payload::from_ref(&referral).get_path(), app_state).is_some()
+}
+
+/// Handles an event received while another app owns the conversation, it is never routed to the user's action.
+async fn dispatch_standby(
+    messaging: Messaging,
+    page_id: String,
+    host: String,
+    platform: Platform,
+    app_state: AppState,
+) {
+    let user = messaging.get_user();
+    app_state.thread_owners.hand_over(user).await;
+    let res = page_res(&page_id, platform, &app_state).await;
+    let message = messaging.get_message().unwrap_or_default();
+    let mut req = Req::new(
+        user,
+        app_state.query.clone(),
+        Data::new(message.get_text(), None),
+        &host,
+    );
+    req.states = app_state.states.clone();
+    req.conversations = app_state.conversations.clone();
+    req.platform = platform;
+    req.page_id = page_id;
+    req.attachments = message.get_attachments();
+    run_hook(Hook::Standby, res, req, &app_state).await;
+}
+
+/// Runs the queued events of the user one after the other, a panicking event does not stop the next ones.
+///
+/// The events run in their own task, the worker is released as soon as one of them waits for an answer in `req.ask`
+/// and the rest of the queue is drained once the user answered.
+async fn drain(mailbox: Mailbox, user: String) {
+    let suspended = Arc::new(Notify::new());
+    let events = suspendable(suspended.clone(), async move {
+        let mut guard = mailbox.guard(&user);
+        while let Some(job) = guard.pop() {
+            if AssertUnwindSafe(job).catch_unwind().await.is_err() {
+                eprintln!("an event of the user {user} panicked");
             }
         }
+    });
+    select(rt::spawn(events), pin!(suspended.notified())).await;
+}
+
+async fn handle(user: &str, job: Job, app_state: &AppState) {
+    let mailbox = &app_state.mailbox;
+    if mailbox.push(user, job) {
+        let drain = drain(mailbox.clone(), user.to_owned());
+        app_state.workers.execute(Box::pin(drain)).await;
     }
 }
 
+/// Gives the text message of the user to the action waiting for it in `req.ask`, if there is one.
+///
+/// A command, a postback, a referral or attachments interrupt the question instead, and so does a message of a user
+/// whose conversation is handed over. The event is then routed as usual.
+async fn resume(messaging: &Messaging, app_state: &AppState) -> bool {
+    let user = messaging.get_user();
+    let conversations = &app_state.conversations;
+    if !conversations.is_waiting(user) {
+        return false;
+    }
+    let text = match messaging.get_message() {
+        Some(message) if message.is_echo() => return false,
+        Some(message) => message.get_text(),
+        None if messaging.get_postback().is_some() || messaging.get_referral().is_some() => {
+            String::new()
+        }
+        None => return false,
+    };
+    let is_command = app_state
+        .commands
+        .iter()
+        .any(|command| command.is_match(&text));
+    if text.is_empty() || is_command || app_state.thread_owners.is_handed_over(user).await {
+        conversations.interrupt(user);
+        return false;
+    }
+    conversations.resume(user, text)
+}
+
 #[post("/webhook")]
 pub async fn webhook_core(
-    data: web::Json<InComingData>,
+    request: HttpRequest,
+    body: web::Bytes,
     app_state: web::Data<AppState>,
     conn: dev::ConnectionInfo,
-) -> &'static str {
-    let query = app_state.query.clone();
-    let user = data.get_sender();
+) -> HttpResponse {
+    let signature = request
+        .headers()
+        .get("X-Hub-Signature-256")
+        .and_then(|signature| signature.to_str().ok());
+    if !verify_signature(signature, &body) {
+        return HttpResponse::Forbidden().body("Signature mismatch");
+    }
+    let data: InComingData = match serde_json::from_slice(&body) {
+        Ok(data) => data,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid payload"),
+    };
     let host = conn.host();
-    query.create(user).await;
-    if ACTION_LOCK.lock(user).await {
-        if let Some(message) = data.get_message() {
-            if let Some(quick_reply) = message.get_quick_reply() {
-                let payload = quick_reply.get_payload();
-                run(Executable::Payload(user, payload, host, query)).await;
-            } else {
-                let text = message.get_text();
-                run(Executable::TextMessage(user, &text, host, query)).await;
+    let platform = data.get_platform();
+    let app_state = app_state.get_ref();
+    for (page_id, messaging) in data.get_messaging() {
+        if let Some(event_id) = messaging.get_event_id() {
+            if app_state.deduplicator.is_duplicate(&event_id).await {
+                continue;
+            }
+        }
+        if resume(messaging, app_state).await {
+            continue;
+        }
+        let job = dispatch(
+            messaging.clone(),
+            page_id.to_owned(),
+            host.to_owned(),
+            platform,
+            app_state.clone(),
+        );
+        handle(messaging.get_user(), Box::pin(job), app_state).await;
+    }
+    for (page_id, messaging) in data.get_standby() {
+        if let Some(event_id) = messaging.get_event_id() {
+            if app_state
+                .deduplicator
+                .is_duplicate(&format!("standby:{event_id}"))
+                .await
+            {
+                continue;
             }
-        } else if let Some(postback) = data.get_postback() {
-            let payload = postback.get_payload();
-            run(Executable::Payload(user, payload, host, query)).await;
         }
+        let job = dispatch_standby(
+            messaging.clone(),
+            page_id.to_owned(),
+            host.to_owned(),
+            platform,
+            app_state.clone(),
+        );
+        handle(messaging.get_user(), Box::pin(job), app_state).await;
     }
-    ACTION_LOCK.unlock(user).await;
-    "Ok"
+    HttpResponse::Ok().body("Ok")
 }
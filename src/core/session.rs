@@ -0,0 +1,57 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::query::Query;
+
+/// The `Session` struct stores typed values for a user, like the content of a cart or the answers of a form.
+///
+/// The values are serialized as JSON and persisted in the `russenger_session` table, they are kept until they are removed.
+///
+/// # Methods
+///
+/// * `get`: Returns the value of a key, or `None` if there is no value or it is not of the requested type.
+/// * `set`: Saves the value of a key, replacing the previous one.
+/// * `remove`: Removes the value of a key.
+///
+/// # Examples
+///
+/// Adding an item to a cart:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn AddToCart(res: Res, req: Req) {
+///     let item: String = req.data.get_value();
+///     let session = req.session();
+///     let mut cart: Vec<String> = session.get("cart").await.unwrap_or_default();
+///     cart.push(item);
+///     session.set("cart", &cart).await;
+///     res.send(TextModel::new(&req.user, &format!("{} item(s) in your cart", cart.len()))).await;
+/// }
+/// ```
+pub struct Session<'s> {
+    user: &'s str,
+    query: &'s Query,
+}
+
+impl<'s> Session<'s> {
+    pub fn new(user: &'s str, query: &'s Query) -> Self {
+        Self { user, query }
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.query.get_session_value(self.user, key).await?;
+        serde_json::from_str(&value).ok()
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> bool {
+        match serde_json::to_string(value) {
+            Ok(value) => self.query.set_session_value(self.user, key, &value).await,
+            Err(_) => false,
+        }
+    }
+
+    pub async fn remove(&self, key: &str) -> bool {
+        self.query.remove_session_value(self.user, key).await
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::query::Query;
+
+/// The `ThreadOwners` struct remembers the conversations handed over to another app through the handover protocol.
+///
+/// While another app owns a conversation, Facebook sends the messages of the user as `standby` events, which never run the bot's actions.
+/// A message received as a `messaging` event proves the bot owns the conversation again, even when it took it back with
+/// `TakeThreadControlModel` and no `pass_thread_control` event was delivered, so the conversation is marked as owned by the bot.
+///
+/// When a `Query` is given, the handed over conversations are also recorded in the `russenger_handover` table, so they survive restarts.
+/// The owner of a conversation is read from the table once, then kept in memory.
+///
+/// # Methods
+///
+/// * `new`: This method creates a new `ThreadOwners` with the optional `Query` used for persistence.
+/// * `hand_over`: This method marks the conversation of a user as owned by another app.
+/// * `take_back`: This method marks the conversation of a user as owned by the bot.
+/// * `is_handed_over`: This method returns `true` if another app owns the conversation of a user.
+#[derive(Clone, Default)]
+pub struct ThreadOwners {
+    handed_over: Arc<Mutex<HashMap<String, bool>>>,
+    query: Option<Query>,
+}
+
+impl ThreadOwners {
+    pub fn new(query: Option<Query>) -> Self {
+        Self {
+            handed_over: Arc::default(),
+            query,
+        }
+    }
+
+    async fn set(&self, user: &str, handed_over: bool) {
+        let previous = self
+            .handed_over
+            .lock()
+            .await
+            .insert(user.to_owned(), handed_over);
+        if previous != Some(handed_over) {
+            if let Some(query) = &self.query {
+                query.set_handed_over(user, handed_over).await;
+            }
+        }
+    }
+
+    pub async fn hand_over(&self, user: &str) {
+        self.set(user, true).await;
+    }
+
+    pub async fn take_back(&self, user: &str) {
+        self.set(user, false).await;
+    }
+
+    pub async fn is_handed_over(&self, user: &str) -> bool {
+        if let Some(handed_over) = self.handed_over.lock().await.get(user) {
+            return *handed_over;
+        }
+        let Some(query) = &self.query else {
+            return false;
+        };
+        match query.is_handed_over(user).await {
+            Some(handed_over) => {
+                let mut owners = self.handed_over.lock().await;
+                *owners.entry(user.to_owned()).or_insert(handed_over)
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::query::DB;
+
+    async fn sqlite() -> Query {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let query = Query {
+            db: DB::Sqlite(pool),
+        };
+        assert!(query.migrate().await);
+        query
+    }
+
+    #[actix_web::test]
+    async fn the_owners_survive_a_restart() {
+        let query = sqlite().await;
+        let owners = ThreadOwners::new(Some(query.clone()));
+        owners.hand_over("u1").await;
+        owners.hand_over("u2").await;
+        owners.take_back("u2").await;
+
+        let restarted = ThreadOwners::new(Some(query.clone()));
+        assert!(restarted.is_handed_over("u1").await);
+        assert!(!restarted.is_handed_over("u2").await);
+        assert!(!restarted.is_handed_over("u3").await);
+        assert_eq!(
+            query.get_session_value("u1", "russenger:handed_over").await,
+            None
+        );
+    }
+
+    #[actix_web::test]
+    async fn the_owners_are_read_once() {
+        let query = sqlite().await;
+        query.set_handed_over("u1", true).await;
+        let owners = ThreadOwners::new(Some(query.clone()));
+        assert!(owners.is_handed_over("u1").await);
+        assert!(!owners.is_handed_over("u2").await);
+
+        query.set_handed_over("u1", false).await;
+        query.set_handed_over("u2", true).await;
+        assert!(owners.is_handed_over("u1").await);
+        assert!(!owners.is_handed_over("u2").await);
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use actix_web::rt::{task, Runtime};
+use tokio::sync::{mpsc, Mutex};
+
+use super::action::Job;
+
+const QUEUE_CAPACITY: usize = 1024;
+
+enum Task {
+    Run(Job),
+    Stop,
+}
+
+/// The `WorkerPool` struct runs jobs in the background on a fixed number of workers.
+///
+/// It lets the webhook answer Facebook at once, while the actions and their calls to the Graph API run later.
+///
+/// Each worker runs on its own thread, with its own runtime, so the jobs of different users run in parallel.
+/// `size` bounds how many jobs run at the same time.
+///
+/// # Methods
+///
+/// * `new`: This method starts `size` worker threads waiting for jobs.
+/// * `execute`: This method queues a job. It waits when the queue is full.
+/// * `shutdown`: This method lets the workers finish every queued job and waits for them to stop.
+#[derive(Clone)]
+pub struct WorkerPool {
+    sender: mpsc::Sender<Task>,
+    workers: Arc<Mutex<Vec<JoinHandle<()>>>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..size.max(1))
+            .filter_map(|index| {
+                let receiver = receiver.clone();
+                let worker = thread::Builder::new()
+                    .name(format!("russenger-worker-{index}"))
+                    .spawn(move || match Runtime::new() {
+                        Ok(runtime) => runtime.block_on(work(receiver)),
+                        Err(err) => eprintln!("the worker {index} could not start: {err}"),
+                    });
+                worker
+                    .map_err(|err| eprintln!("the worker {index} could not start: {err}"))
+                    .ok()
+            })
+            .collect();
+        Self {
+            sender,
+            workers: Arc::new(Mutex::new(workers)),
+        }
+    }
+
+    pub async fn execute(&self, job: Job) {
+        if self.sender.send(Task::Run(job)).await.is_err() {
+            eprintln!("the worker pool is stopped, the job is dropped");
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        let workers = std::mem::take(&mut *self.workers.lock().await);
+        for _ in &workers {
+            self.sender.send(Task::Stop).await.ok();
+        }
+        for worker in workers {
+            task::spawn_blocking(move || worker.join()).await.ok();
+        }
+    }
+}
+
+/// Runs the queued jobs one after the other until the pool stops.
+async fn work(receiver: Arc<Mutex<mpsc::Receiver<Task>>>) {
+    loop {
+        let task = receiver.lock().await.recv().await;
+        match task {
+            Some(Task::Run(job)) => job.await,
+            Some(Task::Stop) | None => break,
+        }
+    }
+}
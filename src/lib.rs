@@ -13,7 +13,7 @@
 //! ## Macros
 //!
 //! - `action`: This proc macro is used to define an action.
-//! - `russenger_app`: This macro is used to create the main application.
+//! - `russenger_app`: This macro is used to create the main application, it builds an `App` with the given actions.
 //!
 //! ## Deprecated
 //!
@@ -45,7 +45,9 @@ pub mod query;
 pub mod response_models;
 
 pub use cli::launch;
-pub use core::action::{Action, ACTION_REGISTRY};
+pub use core::action::{Action, ActionError, ActionResult, FallibleAction, Hook};
+pub use core::app::App;
+pub use core::middleware::{Middleware, Next};
 pub use dotenv::dotenv;
 pub use russenger_macro::action;
 
@@ -103,7 +105,9 @@ macro_rules! create_action {
 
 /// The `russenger_app!` macro is used to create the main application.
 ///
-/// It registers all the provided actions in the `ACTION_REGISTRY` and then starts the command handler.
+/// It builds an `App` with all the provided actions and the optional hooks, and then starts the command handler.
+///
+/// Use the `App` builder directly to share a state with the actions or to configure the server from the code.
 ///
 /// # Syntax
 ///
@@ -127,6 +131,24 @@ macro_rules! create_action {
 ///
 /// * `action1, action2, ..., actionN`: The actions to be registered. These should be instances of structs that implement the `Action` trait.
 ///
+/// The actions handling the `Hook` events are registered after a `;`:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Main(res: Res, req: Req) {
+/// }
+///
+/// #[action]
+/// async fn OnEcho(res: Res, req: Req) {
+///     let text: String = req.data.get_value();
+///     println!("the page sent {text:?} to {}", req.user);
+/// }
+///
+/// russenger_app!(Main; Hook::Echo => OnEcho);
+/// ```
+///
 /// # Examples
 ///
 /// Creating the main application with the `Main` and `Greet` actions:
@@ -151,13 +173,29 @@ macro_rules! create_action {
 /// ```
 #[macro_export]
 macro_rules! russenger_app {
+    ($($action:expr),* $(,)? ; $($hook:expr => $handler:expr),* $(,)?) => {
+        #[allow(unused_imports)]
+        use russenger::Action;
+
+        #[russenger::main]
+        async fn main() {
+            russenger::App::new()
+                $(.action($action))*
+                $(.hook($hook, $handler))*
+                .launch()
+                .await;
+        }
+    };
     ($($action:expr),* $(,)?) => {
-        use russenger::{launch, Action, ACTION_REGISTRY};
+        #[allow(unused_imports)]
+        use russenger::Action;
 
         #[russenger::main]
         async fn main() {
-            $(ACTION_REGISTRY.lock().await.insert($action.path(), Box::new($action));)*
-            launch().await;
+            russenger::App::new()
+                $(.action($action))*
+                .launch()
+                .await;
         }
     };
 }
@@ -3,8 +3,20 @@
 //! # Re-exports
 //!
 //! * `Req`: A struct that represents a request from a user.
+//! * `Platform`: The messaging platform, Messenger or Instagram, of a conversation.
+//! * `Action`, `FallibleAction`, `ActionError`, `ActionResult`: The traits of the actions and of the fallible actions, and the result of the fallible ones.
+//! * `ErrorHandler`, `ErrorMessage`: The handling of the errors returned by the actions.
+//! * `Middleware`, `Next`: The code wrapping the execution of the actions.
+//! * `Hook`: The webhook events, like deliveries, reads and echoes, handled outside of the user's action.
+//! * `Attachment`, `AttachmentType`, `Coordinates`: The files, locations and stickers sent by a user.
+//! * `Referral`: The m.me link or the ad a user comes from.
+//! * `ReactionAction`: Whether a user added or removed a reaction.
+//! * `Handover`: A change of the owner of the conversation.
+//! * `Form`, `Field`: The forms asking the user several questions.
+//! * `Back`: The action going back to the previous action of the user.
+//! * `Session`: The typed values stored for a user, see `req.session()`.
 //! * `Res`, `SendResult`: A struct and a type alias that represent a response that can be sent to a user.
-//! * `Button`, `Data`, `GenericElement`, `GenericModel`, `GetStartedModel`, `MediaModel`, `Payload`, `PersistentMenuModel`, `QuickReply`, `QuickReplyModel`, `SenderActionModel`, `TextModel`, `ResponseModel`: Various response models that can be sent to a user.
+//! * `Button`, `Data`, `GenericElement`, `GenericModel`, `GetStartedModel`, `PassThreadControlModel`, `TakeThreadControlModel`, `RequestThreadControlModel`, `MediaModel`, `Pagination`, `Payload`, `PersistentMenuModel`, `QuickReply`, `QuickReplyModel`, `SenderActionModel`, `TextModel`, `ResponseModel`: Various response models that can be sent to a user.
 //!
 //! # Examples
 //!
@@ -24,20 +36,32 @@
 //! russenger_app!(Main);
 pub use crate::action;
 pub use crate::core::{
+    action::{Action, ActionError, ActionResult, FallibleAction, Hook},
+    error_handler::{ErrorHandler, ErrorMessage},
+    form::{Field, Form},
+    history::Back,
+    incoming_data::{Attachment, AttachmentType, Coordinates, Handover, ReactionAction, Referral},
+    middleware::{Middleware, Next},
     request::Req,
     response::{Res, SendResult},
+    session::Session,
 };
 pub use crate::response_models::{
     button::{Button, ButtonModel},
     data::Data,
     generic::{GenericElement, GenericModel},
     get_started::GetStartedModel,
+    handover::{
+        PassThreadControlModel, RequestThreadControlModel, TakeThreadControlModel,
+        PAGE_INBOX_APP_ID,
+    },
     media::MediaModel,
+    pagination::Pagination,
     payload::Payload,
     persistent_menu::PersistentMenuModel,
     quick_replies::{QuickReply, QuickReplyModel},
     sender_action::{Actions::*, SenderActionModel},
     text::TextModel,
-    ResponseModel,
+    Platform, ResponseModel,
 };
 pub use crate::russenger_app;
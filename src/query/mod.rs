@@ -7,11 +7,11 @@
 //!
 //! ## migrate Method
 //!
-//! The `migrate` method creates a new table `russenger_user` in the database. It returns a boolean indicating whether the operation was successful.
+//! The `migrate` method creates the tables `russenger_user`, `russenger_event`, `russenger_page`, `russenger_session`, `russenger_handover` and `russenger_data` in the database. It returns a boolean indicating whether the operation was successful.
 //!
 //! ## create Method
 //!
-//! The `create` method inserts a new user into the `russenger_user` table. It takes a user ID as an argument and returns a boolean indicating whether the operation was successful.
+//! The `create` method inserts a new user into the `russenger_user` table. It takes a user ID and a page ID as arguments and returns a boolean indicating whether the operation was successful.
 //! 
 //! ## set_action Method
 //!
@@ -91,9 +91,15 @@ macro_rules! execute_query {
 /// # Methods
 ///
 /// * `new`: This method creates a new `Query`. It establishes a connection to the database and returns a `Query` with the established connection.
-/// * `migrate`: This method creates a new table `russenger_user` in the database. It returns a boolean indicating whether the operation was successful.
-/// * `create`: This method inserts a new user into the `russenger_user` table. It takes a user ID as an argument and returns a boolean indicating whether the operation was successful.
+/// * `migrate`: This method creates the tables `russenger_user`, `russenger_event`, `russenger_page`, `russenger_session`, `russenger_handover` and `russenger_data` in the database. It returns a boolean indicating whether the operation was successful.
+/// * `create`: This method inserts a new user into the `russenger_user` table. It takes a user ID and a page ID as arguments and returns a boolean indicating whether the operation was successful.
 /// * `set_action`: This method updates the action of a user in the `russenger_user` table. It takes a user ID and an action as arguments and returns a boolean indicating whether the operation was successful.
+/// * `reset_action`: This method sets the action of a user back to `Main`.
+/// * `save_event`: This method records a processed webhook event in the `russenger_event` table. It returns `Some(false)` if the event was already recorded, and `None` if it can't be recorded.
+/// * `set_page_token` and `get_page_token`: These methods save and retrieve the access token of a page in the `russenger_page` table.
+/// * `set_session_value`, `get_session_value` and `remove_session_value`: These methods save, retrieve and remove the session values of a user in the `russenger_session` table.
+/// * `set_handed_over` and `is_handed_over`: These methods record and tell whether the conversation of a user is handed over to another app, in the `russenger_handover` table.
+/// * `save_data` and `get_data`: These methods save and retrieve the `Data` values stored server-side in the `russenger_data` table.
 #[derive(Clone)]
 pub struct Query {
     pub db: DB,
@@ -114,7 +120,10 @@ impl Query {
         }
     }
 
-    /// Creates a new table `russenger_user` in the database.
+    /// Creates the tables `russenger_user`, `russenger_event`, `russenger_page`, `russenger_session`, `russenger_handover` and `russenger_data` in the database.
+    ///
+    /// Existing tables are kept, so it can be run again after an upgrade to create the new ones.
+    /// The columns added by later versions are added to the existing tables, an already existing column is not an error.
     ///
     /// This method returns a boolean indicating whether the operation was successful.
     ///
@@ -122,50 +131,96 @@ impl Query {
     ///
     /// * `bool`: Whether the operation was successful.
     pub async fn migrate(&self) -> bool {
-        let sql = "
-            create table russenger_user (
+        let statements = [
+            "
+            create table if not exists russenger_user (
                 facebook_user_id varchar(40) primary key unique,
-                action varchar(20)
-            );";
+                action varchar(20),
+                page_id varchar(40)
+            );",
+            "
+            create table if not exists russenger_event (
+                event_id varchar(255) primary key unique,
+                timestamp bigint
+            );",
+            "
+            create table if not exists russenger_page (
+                page_id varchar(40) primary key unique,
+                access_token text
+            );",
+            "
+            create table if not exists russenger_session (
+                facebook_user_id varchar(40),
+                session_key varchar(255),
+                session_value text,
+                primary key (facebook_user_id, session_key)
+            );",
+            "
+            create table if not exists russenger_handover (
+                facebook_user_id varchar(40) primary key unique
+            );",
+            "
+            create table if not exists russenger_data (
+                token varchar(40) primary key unique,
+                data_value text,
+                expires_at bigint
+            );",
+        ];
+        let upgrades = ["alter table russenger_user add column page_id varchar(40);"];
 
         let no_params: [&str; 0] = [];
-        match &self.db {
-            DB::Mysql(pool) => execute_query!(pool, sql, no_params),
-            DB::Sqlite(pool) => execute_query!(pool, sql, no_params),
-            DB::Postgres(pool) => execute_query!(pool, sql, no_params),
-            DB::Null => false,
+        for sql in upgrades {
+            match &self.db {
+                DB::Mysql(pool) => execute_query!(pool, sql, no_params),
+                DB::Sqlite(pool) => execute_query!(pool, sql, no_params),
+                DB::Postgres(pool) => execute_query!(pool, sql, no_params),
+                DB::Null => false,
+            };
+        }
+        let mut success = true;
+        for sql in statements {
+            success &= match &self.db {
+                DB::Mysql(pool) => execute_query!(pool, sql, no_params),
+                DB::Sqlite(pool) => execute_query!(pool, sql, no_params),
+                DB::Postgres(pool) => execute_query!(pool, sql, no_params),
+                DB::Null => false,
+            };
         }
+        success
     }
 
     /// Inserts a new user into the `russenger_user` table.
     ///
-    /// This method takes a user ID as an argument and returns a boolean indicating whether the operation was successful.
+    /// This method takes a user ID and the ID of the page the user is talking to, and returns a boolean indicating whether the operation was successful.
+    /// An existing user keeps its action, and is given the page if it has none, like the users created before the pages were recorded.
     ///
     /// # Arguments
     ///
     /// * `user_id`: The user ID of the new user.
+    /// * `page_id`: The ID of the page receiving the messages of the user.
     ///
     /// # Returns
     ///
     /// * `bool`: Whether the operation was successful.
-    pub async fn create(&self, user_id: &str) -> bool {
-        let params = [user_id, "Main"];
+    pub async fn create(&self, user_id: &str, page_id: &str) -> bool {
+        let params = [user_id, "Main", page_id];
         match &self.db {
             DB::Mysql(pool) => {
-                let sql = "insert into russenger_user (facebook_user_id, action) values (?, ?)";
+                let sql = "insert into russenger_user (facebook_user_id, action, page_id) values (?, ?, ?) on duplicate key update page_id=coalesce(page_id, values(page_id))";
                 execute_query!(pool, sql, params)
             }
             DB::Sqlite(pool) => {
-                let sql = "insert into russenger_user (facebook_user_id, action) values ($1, $2)";
+                let sql = "insert into russenger_user (facebook_user_id, action, page_id) values ($1, $2, $3) on conflict (facebook_user_id) do update set page_id=coalesce(russenger_user.page_id, excluded.page_id)";
                 execute_query!(pool, sql, params)
             }
             DB::Postgres(pool) => {
-                let sql = "insert into russenger_user (facebook_user_id, action) values ($1, $2)";
-                execute_query!(pool, sql, [user_id, "Main"])
+                let sql = "insert into russenger_user (facebook_user_id, action, page_id) values ($1, $2, $3) on conflict (facebook_user_id) do update set page_id=coalesce(russenger_user.page_id, excluded.page_id)";
+                execute_query!(pool, sql, params)
             }
             DB::Null => false,
         }
     }
+
     /// Updates the action of a user in the `russenger_user` table.
     ///
     /// This method takes a user ID and an action as arguments and returns a boolean indicating whether the operation was successful.
@@ -205,7 +260,24 @@ impl Query {
     /// * For SQLite: `"update russenger_user set action=$1 where facebook_user_id=$2"`
     /// * For Postgres: `"update russenger_user set action=$1 where facebook_user_id=$2"`
     pub async fn set_action<A: Action>(&self, user_id: &str, action: A) -> bool {
-        let params = [action.path(), user_id.to_string()];
+        self.set_action_path(user_id, action.path()).await
+    }
+
+    /// Sets the action of a user back to `Main`, the next message of the user starts over.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user whose action is reset.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn reset_action(&self, user_id: &str) -> bool {
+        self.set_action_path(user_id, "Main".to_owned()).await
+    }
+
+    async fn set_action_path(&self, user_id: &str, path: String) -> bool {
+        let params = [path, user_id.to_string()];
         match &self.db {
             DB::Mysql(pool) => {
                 let sql = "update russenger_user set action=? where facebook_user_id=?";
@@ -268,4 +340,428 @@ impl Query {
             DB::Null => None,
         }
     }
+
+    /// Records a processed webhook event in the `russenger_event` table.
+    ///
+    /// Events older than `expired_before` are deleted first, so the table only holds the deduplication window.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_id`: The id of the event, usually the `mid` of the message.
+    /// * `timestamp`: The time of the event, in milliseconds.
+    /// * `expired_before`: The time, in milliseconds, before which events are forgotten.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<bool>`: `Some(true)` if the event was recorded, `Some(false)` if it was already recorded,
+    ///   and `None` if the operation failed, like when the table is missing.
+    pub async fn save_event(
+        &self,
+        event_id: &str,
+        timestamp: i64,
+        expired_before: i64,
+    ) -> Option<bool> {
+        let result = match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "delete from russenger_event where timestamp < ?";
+                sqlx::query(sql)
+                    .bind(expired_before)
+                    .execute(pool)
+                    .await
+                    .ok();
+                let sql = "insert into russenger_event (event_id, timestamp) values (?, ?)";
+                let query = sqlx::query(sql).bind(event_id).bind(timestamp);
+                query.execute(pool).await.map(|_| ())
+            }
+            DB::Sqlite(pool) => {
+                let sql = "delete from russenger_event where timestamp < $1";
+                sqlx::query(sql)
+                    .bind(expired_before)
+                    .execute(pool)
+                    .await
+                    .ok();
+                let sql = "insert into russenger_event (event_id, timestamp) values ($1, $2)";
+                let query = sqlx::query(sql).bind(event_id).bind(timestamp);
+                query.execute(pool).await.map(|_| ())
+            }
+            DB::Postgres(pool) => {
+                let sql = "delete from russenger_event where timestamp < $1";
+                sqlx::query(sql)
+                    .bind(expired_before)
+                    .execute(pool)
+                    .await
+                    .ok();
+                let sql = "insert into russenger_event (event_id, timestamp) values ($1, $2)";
+                let query = sqlx::query(sql).bind(event_id).bind(timestamp);
+                query.execute(pool).await.map(|_| ())
+            }
+            DB::Null => return None,
+        };
+        match result {
+            Ok(()) => Some(true),
+            Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Some(false),
+            Err(err) => {
+                eprintln!("the event {event_id:?} can't be recorded: {err}");
+                None
+            }
+        }
+    }
+
+    /// Saves the access token of a page in the `russenger_page` table.
+    ///
+    /// A bot serving several pages needs one token per page, the token of the page receiving the event is used to answer it.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id`: The ID of the page, or of the Instagram account.
+    /// * `access_token`: The access token of the page.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn set_page_token(&self, page_id: &str, access_token: &str) -> bool {
+        let params = [page_id, access_token];
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "insert into russenger_page (page_id, access_token) values (?, ?) on duplicate key update access_token=values(access_token)";
+                execute_query!(pool, sql, params)
+            }
+            DB::Sqlite(pool) => {
+                let sql = "insert into russenger_page (page_id, access_token) values ($1, $2) on conflict (page_id) do update set access_token=excluded.access_token";
+                execute_query!(pool, sql, params)
+            }
+            DB::Postgres(pool) => {
+                let sql = "insert into russenger_page (page_id, access_token) values ($1, $2) on conflict (page_id) do update set access_token=excluded.access_token";
+                execute_query!(pool, sql, params)
+            }
+            DB::Null => false,
+        }
+    }
+
+    /// Retrieves the access token of a page from the `russenger_page` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_id`: The ID of the page, or of the Instagram account.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>`: The access token of the page, or `None` if the page is not configured.
+    pub async fn get_page_token(&self, page_id: &str) -> Option<String> {
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "select access_token from russenger_page where page_id=?";
+                match sqlx::query(sql).bind(page_id).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Sqlite(pool) => {
+                let sql = "select access_token from russenger_page where page_id=$1";
+                match sqlx::query(sql).bind(page_id).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Postgres(pool) => {
+                let sql = "select access_token from russenger_page where page_id=$1";
+                match sqlx::query(sql).bind(page_id).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Null => None,
+        }
+    }
+
+    /// Saves a session value of a user in the `russenger_session` table, replacing the previous value of the key.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user owning the value.
+    /// * `key`: The name of the value.
+    /// * `value`: The value, serialized as JSON by `Session`.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn set_session_value(&self, user_id: &str, key: &str, value: &str) -> bool {
+        let params = [user_id, key, value];
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "insert into russenger_session (facebook_user_id, session_key, session_value) values (?, ?, ?) on duplicate key update session_value=values(session_value)";
+                execute_query!(pool, sql, params)
+            }
+            DB::Sqlite(pool) => {
+                let sql = "insert into russenger_session (facebook_user_id, session_key, session_value) values ($1, $2, $3) on conflict (facebook_user_id, session_key) do update set session_value=excluded.session_value";
+                execute_query!(pool, sql, params)
+            }
+            DB::Postgres(pool) => {
+                let sql = "insert into russenger_session (facebook_user_id, session_key, session_value) values ($1, $2, $3) on conflict (facebook_user_id, session_key) do update set session_value=excluded.session_value";
+                execute_query!(pool, sql, params)
+            }
+            DB::Null => false,
+        }
+    }
+
+    /// Retrieves a session value of a user from the `russenger_session` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user owning the value.
+    /// * `key`: The name of the value.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>`: The value, or `None` if the user has no value for this key.
+    pub async fn get_session_value(&self, user_id: &str, key: &str) -> Option<String> {
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "select session_value from russenger_session where facebook_user_id=? and session_key=?";
+                match sqlx::query(sql)
+                    .bind(user_id)
+                    .bind(key)
+                    .fetch_one(pool)
+                    .await
+                {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Sqlite(pool) => {
+                let sql = "select session_value from russenger_session where facebook_user_id=$1 and session_key=$2";
+                match sqlx::query(sql)
+                    .bind(user_id)
+                    .bind(key)
+                    .fetch_one(pool)
+                    .await
+                {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Postgres(pool) => {
+                let sql = "select session_value from russenger_session where facebook_user_id=$1 and session_key=$2";
+                match sqlx::query(sql)
+                    .bind(user_id)
+                    .bind(key)
+                    .fetch_one(pool)
+                    .await
+                {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Null => None,
+        }
+    }
+
+    /// Removes a session value of a user from the `russenger_session` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user owning the value.
+    /// * `key`: The name of the value.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn remove_session_value(&self, user_id: &str, key: &str) -> bool {
+        let params = [user_id, key];
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql =
+                    "delete from russenger_session where facebook_user_id=? and session_key=?";
+                execute_query!(pool, sql, params)
+            }
+            DB::Sqlite(pool) => {
+                let sql =
+                    "delete from russenger_session where facebook_user_id=$1 and session_key=$2";
+                execute_query!(pool, sql, params)
+            }
+            DB::Postgres(pool) => {
+                let sql =
+                    "delete from russenger_session where facebook_user_id=$1 and session_key=$2";
+                execute_query!(pool, sql, params)
+            }
+            DB::Null => false,
+        }
+    }
+
+    /// Records in the `russenger_handover` table whether another app owns the conversation of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user.
+    /// * `handed_over`: Whether the conversation is handed over to another app.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn set_handed_over(&self, user_id: &str, handed_over: bool) -> bool {
+        let params = [user_id];
+        match (&self.db, handed_over) {
+            (DB::Mysql(pool), true) => {
+                let sql = "insert ignore into russenger_handover (facebook_user_id) values (?)";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Mysql(pool), false) => {
+                let sql = "delete from russenger_handover where facebook_user_id=?";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Sqlite(pool), true) => {
+                let sql = "insert into russenger_handover (facebook_user_id) values ($1) on conflict (facebook_user_id) do nothing";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Sqlite(pool), false) => {
+                let sql = "delete from russenger_handover where facebook_user_id=$1";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Postgres(pool), true) => {
+                let sql = "insert into russenger_handover (facebook_user_id) values ($1) on conflict (facebook_user_id) do nothing";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Postgres(pool), false) => {
+                let sql = "delete from russenger_handover where facebook_user_id=$1";
+                execute_query!(pool, sql, params)
+            }
+            (DB::Null, _) => false,
+        }
+    }
+
+    /// Tells from the `russenger_handover` table whether another app owns the conversation of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The user ID of the user.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<bool>`: Whether the conversation is handed over, or `None` if the table can't be read.
+    pub async fn is_handed_over(&self, user_id: &str) -> Option<bool> {
+        let row = match &self.db {
+            DB::Mysql(pool) => {
+                let sql =
+                    "select facebook_user_id from russenger_handover where facebook_user_id=?";
+                sqlx::query(sql)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map(|row| row.is_some())
+            }
+            DB::Sqlite(pool) => {
+                let sql =
+                    "select facebook_user_id from russenger_handover where facebook_user_id=$1";
+                sqlx::query(sql)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map(|row| row.is_some())
+            }
+            DB::Postgres(pool) => {
+                let sql =
+                    "select facebook_user_id from russenger_handover where facebook_user_id=$1";
+                sqlx::query(sql)
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await
+                    .map(|row| row.is_some())
+            }
+            DB::Null => return None,
+        };
+        row.ok()
+    }
+
+    /// Saves a `Data` value stored server-side in the `russenger_data` table.
+    ///
+    /// The values expired before `now` are deleted first.
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: The token identifying the value in the payloads.
+    /// * `value`: The serialized value.
+    /// * `expires_at`: The time, in milliseconds, after which the value is forgotten.
+    /// * `now`: The current time, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * `bool`: Whether the operation was successful.
+    pub async fn save_data(&self, token: &str, value: &str, expires_at: i64, now: i64) -> bool {
+        let (cleaned, saved) = match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "delete from russenger_data where expires_at < ?";
+                let cleaned = sqlx::query(sql).bind(now).execute(pool).await;
+                let sql =
+                    "insert into russenger_data (token, data_value, expires_at) values (?, ?, ?)";
+                let query = sqlx::query(sql).bind(token).bind(value).bind(expires_at);
+                (cleaned.map(|_| ()), query.execute(pool).await.map(|_| ()))
+            }
+            DB::Sqlite(pool) => {
+                let sql = "delete from russenger_data where expires_at < $1";
+                let cleaned = sqlx::query(sql).bind(now).execute(pool).await;
+                let sql =
+                    "insert into russenger_data (token, data_value, expires_at) values ($1, $2, $3)";
+                let query = sqlx::query(sql).bind(token).bind(value).bind(expires_at);
+                (cleaned.map(|_| ()), query.execute(pool).await.map(|_| ()))
+            }
+            DB::Postgres(pool) => {
+                let sql = "delete from russenger_data where expires_at < $1";
+                let cleaned = sqlx::query(sql).bind(now).execute(pool).await;
+                let sql =
+                    "insert into russenger_data (token, data_value, expires_at) values ($1, $2, $3)";
+                let query = sqlx::query(sql).bind(token).bind(value).bind(expires_at);
+                (cleaned.map(|_| ()), query.execute(pool).await.map(|_| ()))
+            }
+            DB::Null => return false,
+        };
+        if let Err(err) = cleaned {
+            eprintln!("the expired data can't be deleted: {err}");
+        }
+        match saved {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("the data {token:?} can't be saved: {err}");
+                false
+            }
+        }
+    }
+
+    /// Retrieves a `Data` value stored server-side from the `russenger_data` table.
+    ///
+    /// # Arguments
+    ///
+    /// * `token`: The token identifying the value in the payloads.
+    /// * `now`: The current time, in milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>`: The serialized value, or `None` if the token is unknown or the value expired.
+    pub async fn get_data(&self, token: &str, now: i64) -> Option<String> {
+        match &self.db {
+            DB::Mysql(pool) => {
+                let sql = "select data_value from russenger_data where token=? and expires_at >= ?";
+                match sqlx::query(sql).bind(token).bind(now).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Sqlite(pool) => {
+                let sql =
+                    "select data_value from russenger_data where token=$1 and expires_at >= $2";
+                match sqlx::query(sql).bind(token).bind(now).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Postgres(pool) => {
+                let sql =
+                    "select data_value from russenger_data where token=$1 and expires_at >= $2";
+                match sqlx::query(sql).bind(token).bind(now).fetch_one(pool).await {
+                    Ok(row) => row.get(0),
+                    Err(_) => None,
+                }
+            }
+            DB::Null => None,
+        }
+    }
 }
@@ -2,7 +2,8 @@ use serde::Serialize;
 use serde_json::json;
 use serde_json::value::Value;
 
-use super::{payload::Payload, recipient::Recipient, ResponseModel};
+use super::payload::{Payload, MAX_PAYLOAD_LENGTH};
+use super::{recipient::Recipient, Platform, ResponseModel};
 
 /// `Button` is an enum that represents different types of buttons that can be used in a Messenger conversation.
 ///
@@ -88,6 +89,29 @@ impl Button {
     }
 }
 
+/// Instagram does not support the account linking buttons, and the payloads are limited to `MAX_PAYLOAD_LENGTH` characters.
+pub(crate) fn check_buttons(buttons: &[Value], platform: Platform) -> Result<(), String> {
+    let mut payloads = buttons
+        .iter()
+        .filter_map(|button| button["payload"].as_str());
+    if payloads.any(|payload| payload.chars().count() > MAX_PAYLOAD_LENGTH) {
+        return Err(format!(
+            "payloads are limited to {MAX_PAYLOAD_LENGTH} characters, store the data with `Data::store`"
+        ));
+    }
+    let unsupported = ["account_link", "account_unlink"];
+    match platform {
+        Platform::Instagram
+            if buttons.iter().any(|button| {
+                unsupported.contains(&button["type"].as_str().unwrap_or_default())
+            }) =>
+        {
+            Err("account link buttons are not supported on Instagram".to_owned())
+        }
+        _ => Ok(()),
+    }
+}
+
 #[derive(Serialize)]
 struct ButtonPayload<'p> {
     template_type: &'p str,
@@ -182,4 +206,9 @@ impl<'b> ButtonModel<'b> {
 
 impl ResponseModel for ButtonModel<'_> {
     const END_POINT: &'static str = "messages";
+
+    fn check(&self, platform: Platform) -> Result<(), String> {
+        let buttons = self.message["attachment"]["payload"]["buttons"].as_array();
+        check_buttons(buttons.map(Vec::as_slice).unwrap_or_default(), platform)
+    }
 }
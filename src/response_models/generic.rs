@@ -3,10 +3,10 @@ use serde::Serialize;
 use serde_json::value::Value;
 
 use super::{
-    button::Button,
+    button::{check_buttons, Button},
     data::{Page, MAX_PAGE},
     recipient::Recipient,
-    ResponseModel,
+    Platform, ResponseModel,
 };
 
 /// `GenericElement` is a struct that represents a single element in a generic template.
@@ -262,4 +262,14 @@ impl<'g> GenericModel<'g> {
 
 impl ResponseModel for GenericModel<'_> {
     const END_POINT: &'static str = "messages";
+
+    fn check(&self, platform: Platform) -> Result<(), String> {
+        let elements = &self.message.attachment.payload.elements;
+        if elements.len() > MAX_PAGE {
+            return Err(format!("generic elements are limited to {MAX_PAGE}"));
+        }
+        elements
+            .iter()
+            .try_for_each(|element| check_buttons(&element.buttons, platform))
+    }
 }
@@ -0,0 +1,157 @@
+use serde::Serialize;
+
+use super::recipient::Recipient;
+use super::ResponseModel;
+
+/// The app id of the Page Inbox, the human agents answer the users from there.
+pub const PAGE_INBOX_APP_ID: u64 = 263902037430900;
+
+/// `PassThreadControlModel` is used to pass the control of the conversation to another app, like the Page Inbox.
+///
+/// While another app owns the conversation, the bot's actions are not run for this user.
+///
+/// # Fields
+///
+/// * `recipient: Recipient<'p>` - The user of the conversation.
+/// * `target_app_id: u64` - The app receiving the control of the conversation.
+/// * `metadata: Option<&'p str>` - Optional data sent to the target app.
+///
+/// # Methods
+///
+/// * `new(sender: &'p str, target_app_id: u64, metadata: Option<&'p str>) -> Self` - Creates a new `PassThreadControlModel` instance.
+///
+/// # Examples
+///
+/// Handing the conversation to a human agent:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn TalkToHuman(res: Res, req: Req) {
+///     res.send(TextModel::new(&req.user, "An agent will answer you soon.")).await;
+///     res.send(PassThreadControlModel::new(&req.user, PAGE_INBOX_APP_ID, None)).await;
+/// }
+/// ```
+///
+/// [Facebook Documentation](https://developers.facebook.com/docs/messenger-platform/handover-protocol/pass-thread-control)
+#[derive(Debug, Clone, Serialize)]
+pub struct PassThreadControlModel<'p> {
+    recipient: Recipient<'p>,
+    target_app_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<&'p str>,
+}
+
+impl<'p> PassThreadControlModel<'p> {
+    /// Creates a new `PassThreadControlModel` instance.
+    ///
+    /// # Parameters
+    ///
+    /// * `sender: &'p str` - The user ID of the conversation.
+    /// * `target_app_id: u64` - The app receiving the control, `PAGE_INBOX_APP_ID` for the Page Inbox.
+    /// * `metadata: Option<&'p str>` - Optional data sent to the target app.
+    ///
+    /// # Returns
+    ///
+    /// A new `PassThreadControlModel` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::response_models::handover::{PassThreadControlModel, PAGE_INBOX_APP_ID};
+    ///
+    /// PassThreadControlModel::new("sender_id", PAGE_INBOX_APP_ID, Some("refund request"));
+    /// ```
+    pub fn new(sender: &'p str, target_app_id: u64, metadata: Option<&'p str>) -> Self {
+        Self {
+            recipient: Recipient { id: sender },
+            target_app_id,
+            metadata,
+        }
+    }
+}
+
+impl ResponseModel for PassThreadControlModel<'_> {
+    const END_POINT: &'static str = "pass_thread_control";
+}
+
+/// `TakeThreadControlModel` is used by the primary receiver app to take back the control of the conversation.
+///
+/// # Fields
+///
+/// * `recipient: Recipient<'t>` - The user of the conversation.
+/// * `metadata: Option<&'t str>` - Optional data sent to the app losing the control.
+///
+/// # Methods
+///
+/// * `new(sender: &'t str, metadata: Option<&'t str>) -> Self` - Creates a new `TakeThreadControlModel` instance.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::response_models::handover::TakeThreadControlModel;
+///
+/// TakeThreadControlModel::new("sender_id", None);
+/// ```
+///
+/// [Facebook Documentation](https://developers.facebook.com/docs/messenger-platform/handover-protocol/take-thread-control)
+#[derive(Debug, Clone, Serialize)]
+pub struct TakeThreadControlModel<'t> {
+    recipient: Recipient<'t>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<&'t str>,
+}
+
+impl<'t> TakeThreadControlModel<'t> {
+    pub fn new(sender: &'t str, metadata: Option<&'t str>) -> Self {
+        Self {
+            recipient: Recipient { id: sender },
+            metadata,
+        }
+    }
+}
+
+impl ResponseModel for TakeThreadControlModel<'_> {
+    const END_POINT: &'static str = "take_thread_control";
+}
+
+/// `RequestThreadControlModel` is used by a secondary receiver app to ask the primary receiver for the control of the conversation.
+///
+/// # Fields
+///
+/// * `recipient: Recipient<'r>` - The user of the conversation.
+/// * `metadata: Option<&'r str>` - Optional data sent to the primary receiver.
+///
+/// # Methods
+///
+/// * `new(sender: &'r str, metadata: Option<&'r str>) -> Self` - Creates a new `RequestThreadControlModel` instance.
+///
+/// # Examples
+///
+/// ```rust
+/// use russenger::response_models::handover::RequestThreadControlModel;
+///
+/// RequestThreadControlModel::new("sender_id", Some("the user asked for the bot"));
+/// ```
+///
+/// [Facebook Documentation](https://developers.facebook.com/docs/messenger-platform/handover-protocol/request-thread-control)
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestThreadControlModel<'r> {
+    recipient: Recipient<'r>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<&'r str>,
+}
+
+impl<'r> RequestThreadControlModel<'r> {
+    pub fn new(sender: &'r str, metadata: Option<&'r str>) -> Self {
+        Self {
+            recipient: Recipient { id: sender },
+            metadata,
+        }
+    }
+}
+
+impl ResponseModel for RequestThreadControlModel<'_> {
+    const END_POINT: &'static str = "request_thread_control";
+}
@@ -8,8 +8,10 @@
 //! * `sender_action`: This module contains the `SenderActionModel` struct.
 //! * `generic`: This module contains the `GenericTemplateModel` struct.
 //! * `get_started`: This module contains the `GetStartedModel` struct.
+//! * `handover`: This module contains the `PassThreadControlModel`, `TakeThreadControlModel` and `RequestThreadControlModel` structs.
 //! * `media`: This module contains the `MediaModel` struct.
 //! * `payload`: This module contains the `PayloadModel` struct.
+//! * `pagination`: This module contains the `Pagination` struct.
 //! * `persistent_menu`: This module contains the `PersistentMenuModel` struct.
 //! * `quick_replies`: This module contains the `QuickRepliesModel` struct.
 //! * `recipient`: This module contains the `RecipientModel` struct.
@@ -17,15 +19,19 @@
 //!
 //! # Traits
 //!
-//! * `ResponseModel`: This trait is implemented by all response models. It has a `get_endpoint` method that returns the endpoint to which the response should be sent.
+//! * `ResponseModel`: This trait is implemented by all response models. It has a `get_endpoint` method that returns the endpoint to which the response should be sent, and a `check` method that enforces the limits of the `Platform`.
+//!
+//! # Enums
+//!
+//! * `Platform`: The messaging platform, Messenger or Instagram, a response is sent to.
 //!
 //! # Structs
 //!
-//! * `Page`: This struct represents a page of responses. It has a `next` method that moves to the next page and a `default` method that returns the first page.
+//! * `Page`: This struct represents a page of responses. It has `next` and `previous` methods that move to the next and the previous page, and a `default` method that returns the first page.
 //!
 //! # Constants
 //!
-//! * `MAX_VALUE_AUTORIZED`: The maximum value that is authorized.
+//! * `DATA_EXPIRY`: How long the values stored with `Data::store` are kept.
 //! * `MIN_PAGE`: The minimum page number.
 //! * `MAX_PAGE`: The maximum page number.
 //!
@@ -45,7 +51,9 @@
 pub mod button;
 pub mod generic;
 pub mod get_started;
+pub mod handover;
 pub mod media;
+pub mod pagination;
 pub mod payload;
 pub mod persistent_menu;
 pub mod quick_replies;
@@ -55,28 +63,79 @@ pub mod text;
 
 use serde::Serialize;
 
+/// `Platform` is the messaging platform a conversation happens on.
+///
+/// The same actions and response models serve both platforms, the models are checked against the limits of the platform before being sent.
+///
+/// # Variants
+///
+/// * `Messenger` - A Facebook Page conversation.
+/// * `Instagram` - An Instagram professional account conversation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Platform {
+    #[default]
+    Messenger,
+    Instagram,
+}
+
+impl Platform {
+    /// Returns the maximum number of characters of a text message.
+    pub fn max_text_length(&self) -> usize {
+        match self {
+            Platform::Messenger => 2000,
+            Platform::Instagram => 1000,
+        }
+    }
+}
+
 pub trait ResponseModel: Serialize {
     const END_POINT: &'static str;
 
     fn get_endpoint(&self) -> &'static str {
         Self::END_POINT
     }
+
+    /// Checks the model against the limits of the platform it is sent to.
+    ///
+    /// `Res::send` does not send a model failing this check.
+    fn check(&self, _platform: Platform) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 pub mod data {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
     use serde::{Deserialize, Serialize};
 
-    const MAX_VALUE_AUTORIZED: usize = 500;
+    use crate::query::Query;
+
     const MIN_PAGE: usize = 0;
+    /// How long the values stored with `Data::store` are kept.
+    pub const DATA_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
     pub const MAX_PAGE: usize = 10;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Page(pub usize, pub usize);
 
     impl Page {
+        /// Moves to the next page, a page of the same size.
         pub fn next(&mut self) {
-            self.0 += MAX_PAGE;
-            self.1 += MAX_PAGE;
+            let size = self.size();
+            self.0 += size;
+            self.1 += size;
+        }
+
+        /// Moves to the previous page, it stays on the first page.
+        pub fn previous(&mut self) {
+            let size = self.size();
+            self.0 = self.0.saturating_sub(size);
+            self.1 = self.0 + size;
+        }
+
+        /// Returns the number of items of the page.
+        pub fn size(&self) -> usize {
+            self.1.saturating_sub(self.0)
         }
     }
 
@@ -86,18 +145,11 @@ pub mod data {
         }
     }
 
-    trait Verify: ToString {
-        fn verify(&self) -> String;
-    }
-
-    impl Verify for String {
-        fn verify(&self) -> String {
-            if self.len() >= MAX_VALUE_AUTORIZED {
-                self[..MAX_VALUE_AUTORIZED].to_string()
-            } else {
-                self.clone()
-            }
-        }
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64
     }
 
     /// The `Data` struct represents a data object with a value and an optional page.
@@ -114,6 +166,7 @@ pub mod data {
     /// * `new`: This method creates a new `Data`. It takes a value and an optional page as arguments, serializes the value into a JSON string, and returns a `Data` with the serialized string and the page.
     /// * `get_value`: This method deserializes the value of the data into a specified type. It returns the deserialized value if it exists, or the default value of the type if it doesn't.
     /// * `get_page`: This method returns the page of the data.
+    /// * `store`, `store_for`: These methods move the value to the database, the payload then only carries a token.
     ///
     /// # Examples
     ///
@@ -128,8 +181,11 @@ pub mod data {
     /// ```
     #[derive(Debug, Default, Clone, Deserialize, Serialize)]
     pub struct Data {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
         value: String,
         page: Option<Page>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
     }
 
     impl Data {
@@ -147,8 +203,89 @@ pub mod data {
         /// * `Data`: The created `Data`.
         ///
         pub fn new<T: Serialize>(value: T, page: Option<Page>) -> Self {
-            let value = serde_json::to_string(&value).unwrap_or_default().verify();
-            Self { value, page }
+            let value = serde_json::to_string(&value).unwrap_or_default();
+            Self {
+                value,
+                page,
+                token: None,
+            }
+        }
+
+        /// Stores the value in the `russenger_data` table for `DATA_EXPIRY`, see `store_for`.
+        pub async fn store(self, query: &Query) -> Self {
+            self.store_for(query, DATA_EXPIRY).await
+        }
+
+        /// Stores the value in the `russenger_data` table, the payload only carries a short token identifying it.
+        ///
+        /// The payloads of the buttons and the quick replies are limited to 1000 characters and can be read by the user,
+        /// a stored value has no size limit and stays on the server. The value is loaded back before the action runs,
+        /// an expired value is logged and reads as the default value of its type.
+        ///
+        /// The value stays in the payload if it can't be stored.
+        ///
+        /// # Examples
+        ///
+        /// ```rust
+        /// use russenger::prelude::*;
+        /// use serde::{Deserialize, Serialize};
+        ///
+        /// #[derive(Default, Serialize, Deserialize)]
+        /// struct Cart {
+        ///     products: Vec<String>,
+        /// }
+        ///
+        /// #[action]
+        /// async fn Main(res: Res, req: Req) {
+        ///     let cart = Cart { products: vec!["T-shirt".into(); 100] };
+        ///     let data = Data::new(cart, None).store(&req.query).await;
+        ///     let buttons = vec![Button::Postback { title: "Checkout".into(), payload: Payload::new(Checkout, Some(data)) }];
+        ///     res.send(ButtonModel::new(&req.user, "Your cart is ready", buttons)).await;
+        /// }
+        ///
+        /// #[action]
+        /// async fn Checkout(res: Res, req: Req) {
+        ///     let cart: Cart = req.data.get_value();
+        ///     let text = format!("{} products", cart.products.len());
+        ///     res.send(TextModel::new(&req.user, &text)).await;
+        /// }
+        /// ```
+        pub async fn store_for(self, query: &Query, expiry: Duration) -> Self {
+            let token = hex::encode(rand::random::<[u8; 16]>());
+            let expires_at = now() + expiry.as_millis() as i64;
+            if !query
+                .save_data(&token, &self.value, expires_at, now())
+                .await
+            {
+                eprintln!("the data can't be stored, it stays in the payload");
+                return self;
+            }
+            Self {
+                value: String::new(),
+                page: self.page,
+                token: Some(token),
+            }
+        }
+
+        /// Returns `true` if the data holds no value, like the data of a payload created without data.
+        pub(crate) fn is_empty(&self) -> bool {
+            self.value.is_empty() && self.token.is_none()
+        }
+
+        /// Loads the value stored with `store`, the data is returned as is if it was not stored.
+        pub(crate) async fn load(self, query: &Query) -> Self {
+            let Some(token) = &self.token else {
+                return self;
+            };
+            let value = query.get_data(token, now()).await;
+            if value.is_none() {
+                eprintln!("the data {token:?} expired or does not exist");
+            }
+            Self {
+                value: value.unwrap_or_default(),
+                page: self.page,
+                token: None,
+            }
         }
 
         /// Deserializes the value of the data into a specified type.
@@ -193,4 +330,68 @@ pub mod data {
             self.page.clone()
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use sqlx::sqlite::SqlitePoolOptions;
+
+        use super::*;
+        use crate::query::DB;
+
+        async fn sqlite() -> Query {
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap();
+            let query = Query {
+                db: DB::Sqlite(pool),
+            };
+            assert!(query.migrate().await);
+            query
+        }
+
+        #[actix_web::test]
+        async fn a_stored_value_is_loaded_back() {
+            let query = sqlite().await;
+            let data = Data::new("a long value", Some(Page(5, 10)))
+                .store(&query)
+                .await;
+            assert!(data.value.is_empty() && data.token.is_some());
+
+            let data = data.load(&query).await;
+            assert_eq!(data.get_value::<String>(), "a long value");
+            assert_eq!(data.get_page().map(|page| page.0), Some(5));
+            assert!(data.token.is_none());
+        }
+
+        #[actix_web::test]
+        async fn an_unknown_token_loads_no_value() {
+            let query = sqlite().await;
+            let data = Data {
+                value: String::new(),
+                page: None,
+                token: Some("unknown".to_owned()),
+            };
+            assert!(data.load(&query).await.is_empty());
+        }
+
+        #[actix_web::test]
+        async fn the_values_expire() {
+            let query = sqlite().await;
+            let data = Data::new("short lived", None)
+                .store_for(&query, Duration::ZERO)
+                .await;
+            let token = data.token.clone().unwrap();
+            actix_web::rt::time::sleep(Duration::from_millis(5)).await;
+            assert!(data.load(&query).await.is_empty());
+
+            assert_eq!(
+                query.get_data(&token, 0).await.as_deref(),
+                Some("\"short lived\"")
+            );
+            assert!(query.save_data("other", "1", now() + 1000, now()).await);
+            assert_eq!(query.get_data(&token, 0).await, None);
+        }
+    }
 }
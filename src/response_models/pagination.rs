@@ -0,0 +1,246 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::{data::Data, data::Page, payload::Payload, quick_replies::QuickReply};
+use crate::Action;
+
+/// `Pagination` splits a list of `total` items into pages of `size` items, and builds the quick replies moving between them.
+///
+/// The current page comes from `req.data.get_page()`, the first page is shown when there is none.
+/// The same `Pagination` serves a `GenericModel`, a list written in a `TextModel` or the quick replies of a `QuickReplyModel`:
+/// `slice` keeps the items of the current page, and `quick_replies` builds the `Previous`, `Page x of y` and `Next` quick replies.
+/// `Previous` is not offered on the first page and `Next` is not offered on the last one.
+///
+/// # Methods
+///
+/// * `new(page: Option<Page>, total: usize, size: usize) -> Self` - Creates the pagination of `total` items, on the given page.
+/// * `get_page() -> Page` - Returns the current page, to give to `GenericModel::new`.
+/// * `current() -> usize` - Returns the number of the current page, starting at 1.
+/// * `count() -> usize` - Returns the number of pages.
+/// * `has_previous() -> bool`, `has_next() -> bool` - Tell whether there is a page before or after the current one.
+/// * `range() -> Range<usize>` - Returns the indexes of the items of the current page.
+/// * `slice(items: &[T]) -> &[T]` - Keeps the items of the current page.
+/// * `quick_replies(action, value) -> Vec<QuickReply>` - Builds the navigation quick replies, they run `action` again with `value` on another page.
+/// * `labels(previous, indicator, next) -> Self` - Replaces the titles of the quick replies, `indicator` is formatted with `{current}` and `{count}`.
+///
+/// # Examples
+///
+/// Paginating a text list, 5 products per page:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Products(res: Res, req: Req) {
+///     let products: Vec<String> = (1..=23).map(|i| format!("Product {i}")).collect();
+///     let pagination = Pagination::new(req.data.get_page(), products.len(), 5);
+///
+///     let list = pagination.slice(&products).join("\n");
+///     let quick_replies = pagination.quick_replies(&Products, "");
+///     res.send(QuickReplyModel::new(&req.user, &list, quick_replies)).await;
+/// }
+/// ```
+///
+/// Paginating a `GenericModel`, which shows at most `MAX_PAGE` (10) elements, so the `size` must not be above 10:
+///
+/// ```rust
+/// use russenger::prelude::*;
+///
+/// #[action]
+/// async fn Catalog(res: Res, req: Req) {
+///     let elements: Vec<GenericElement> = (1..=30)
+///         .map(|i| GenericElement::new(&format!("Item {i}"), "https://example.com/image.jpg", "", vec![]))
+///         .collect();
+///     let pagination = Pagination::new(req.data.get_page(), elements.len(), 10);
+///
+///     res.send(GenericModel::new(&req.user, elements, Some(pagination.get_page()))).await;
+///     Catalog.paginate(res, req, pagination).await;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pagination {
+    index: usize,
+    size: usize,
+    total: usize,
+    labels: [String; 3],
+}
+
+impl Pagination {
+    /// Creates the pagination of `total` items in pages of `size` items.
+    ///
+    /// A page past the end of the list is replaced by the last page, and a `size` of 0 is taken as 1.
+    /// The `size` is not capped: a `GenericModel` shows at most `MAX_PAGE` (10) elements and fails its check with bigger pages,
+    /// a list written in a `TextModel` can use bigger pages.
+    pub fn new(page: Option<Page>, total: usize, size: usize) -> Self {
+        let size = size.max(1);
+        let start = page.map(|page| page.0).unwrap_or_default();
+        let mut pagination = Self {
+            index: start / size,
+            size,
+            total,
+            labels: [
+                "Previous".into(),
+                "Page {current} of {count}".into(),
+                "Next".into(),
+            ],
+        };
+        pagination.index = pagination.index.min(pagination.count() - 1);
+        pagination
+    }
+
+    pub fn labels(mut self, previous: &str, indicator: &str, next: &str) -> Self {
+        self.labels = [previous.into(), indicator.into(), next.into()];
+        self
+    }
+
+    fn page(&self, index: usize) -> Page {
+        let start = index * self.size;
+        Page(start, start + self.size)
+    }
+
+    pub fn get_page(&self) -> Page {
+        self.page(self.index)
+    }
+
+    pub fn current(&self) -> usize {
+        self.index + 1
+    }
+
+    pub fn count(&self) -> usize {
+        self.total.div_ceil(self.size).max(1)
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.current() < self.count()
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        let start = (self.index * self.size).min(self.total);
+        start..(start + self.size).min(self.total)
+    }
+
+    pub fn slice<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+        let range = self.range();
+        &items[range.start.min(items.len())..range.end.min(items.len())]
+    }
+
+    /// Builds the `Previous`, `Page x of y` and `Next` quick replies, none when the list fits in one page.
+    ///
+    /// Each quick reply runs `action` with `value` in `req.data`, and the page to show.
+    pub fn quick_replies<A, T>(&self, action: &A, value: T) -> Vec<QuickReply>
+    where
+        A: Action + ?Sized,
+        T: Serialize,
+    {
+        if self.count() == 1 {
+            return Vec::new();
+        }
+        let path = action.path();
+        let value = serde_json::to_value(value).unwrap_or_default();
+        let quick_reply = |title: &str, index: usize| {
+            let data = Data::new(&value, Some(self.page(index)));
+            QuickReply::new(
+                title,
+                "",
+                Payload {
+                    path: path.clone(),
+                    data: Some(data),
+                },
+            )
+        };
+        let [previous, indicator, next] = &self.labels;
+        let indicator = indicator
+            .replace("{current}", &self.current().to_string())
+            .replace("{count}", &self.count().to_string());
+        let mut quick_replies = Vec::new();
+        if self.has_previous() {
+            quick_replies.push(quick_reply(previous, self.index - 1));
+        }
+        quick_replies.push(quick_reply(&indicator, self.index));
+        if self.has_next() {
+            quick_replies.push(quick_reply(next, self.index + 1));
+        }
+        quick_replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::request::Req;
+    use crate::core::response::Res;
+
+    struct Products;
+
+    #[async_trait::async_trait]
+    impl Action for Products {
+        async fn execute(&self, _res: Res, _req: Req) {}
+
+        fn path(&self) -> String {
+            "Products".to_owned()
+        }
+    }
+
+    fn titles(pagination: &Pagination) -> Vec<String> {
+        let quick_replies = pagination.quick_replies(&Products, "");
+        let quick_replies = serde_json::to_value(quick_replies).unwrap();
+        let quick_replies = quick_replies.as_array().unwrap();
+        quick_replies
+            .iter()
+            .map(|quick_reply| quick_reply["title"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn an_empty_list_has_one_page() {
+        let pagination = Pagination::new(None, 0, 5);
+        assert_eq!(pagination.count(), 1);
+        assert_eq!(pagination.range(), 0..0);
+        assert!(pagination.slice::<u8>(&[]).is_empty());
+        assert!(titles(&pagination).is_empty());
+    }
+
+    #[test]
+    fn a_size_of_zero_is_taken_as_one() {
+        let pagination = Pagination::new(None, 3, 0);
+        assert_eq!(pagination.count(), 3);
+        assert_eq!(pagination.slice(&[1, 2, 3]), &[1]);
+    }
+
+    #[test]
+    fn a_page_past_the_end_is_the_last_page() {
+        let pagination = Pagination::new(Some(Page(100, 105)), 23, 5);
+        assert_eq!(pagination.current(), 5);
+        assert_eq!(pagination.range(), 20..23);
+        assert_eq!(pagination.get_page().0, 20);
+    }
+
+    #[test]
+    fn the_first_and_last_pages_have_one_direction() {
+        let first = Pagination::new(None, 23, 5);
+        assert!(!first.has_previous() && first.has_next());
+        assert_eq!(titles(&first), ["Page 1 of 5", "Next"]);
+
+        let middle = Pagination::new(Some(Page(5, 10)), 23, 5);
+        assert_eq!(titles(&middle), ["Previous", "Page 2 of 5", "Next"]);
+
+        let last = Pagination::new(Some(Page(20, 25)), 23, 5);
+        assert!(last.has_previous() && !last.has_next());
+        assert_eq!(titles(&last), ["Previous", "Page 5 of 5"]);
+    }
+
+    #[test]
+    fn the_labels_can_be_replaced() {
+        let pagination = Pagination::new(Some(Page(5, 10)), 23, 5).labels(
+            "Précédent",
+            "{current}/{count}",
+            "Suivant",
+        );
+        assert_eq!(titles(&pagination), ["Précédent", "2/5", "Suivant"]);
+    }
+}
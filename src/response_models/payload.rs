@@ -1,10 +1,19 @@
+use std::fmt;
 use std::str::FromStr;
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 use super::data::Data;
 use crate::Action;
 
+type HmacSha256 = Hmac<Sha256>;
+
+/// The maximum number of characters of the payload of a button or a quick reply.
+pub(crate) const MAX_PAYLOAD_LENGTH: usize = 1000;
+
 /// `Payload` is a struct that represents the payload of a request in a Messenger conversation.
 ///
 /// The payload contains the path of the action to be performed and optional data associated with the action.
@@ -19,6 +28,20 @@ use crate::Action;
 /// * `new<A: Action>(action: A, data: Option<Data>) -> Self` - Creates a new `Payload` instance.
 ///   The `action` parameter is the action to be performed, and the `data` parameter is the data associated with the action.
 /// * `get_data(&self) -> Data` - Returns the data associated with the action. If there is no data, it returns the default value of `Data`.
+/// * `to_ref(&self) -> String` - Encodes the payload into the `ref` parameter of an m.me link.
+/// * `from_ref(referral: &str) -> Self` - Decodes the `ref` parameter of an m.me link or of an ad.
+/// * `is_signed() -> bool` - Returns `true` if the payloads are signed.
+/// * `m_me_link(&self, page: &str) -> String` - Builds the m.me link opening a conversation with the page on this payload.
+/// * `is_authentic(payload: &str) -> bool` - Checks the signature of a serialized payload.
+///
+/// # Signature
+///
+/// The payloads come back from the user, who can change them. Signing is opt-in: when the `PAYLOAD_SECRET` env variable is set,
+/// the serialized payload carries an HMAC-SHA256 signature of its path and data, and the payloads with a missing or wrong signature
+/// are not routed to their action but to the `rejected_payload` action of the `App`.
+/// The `ref` of the referrals must then be built by `to_ref` or `m_me_link`: a plain `ref`, like `Promo` in `m.me/page?ref=Promo`,
+/// has no signature and goes to the `rejected_payload` action too, with the `ref` in `req.data`.
+/// Setting or changing the secret rejects the buttons sent before, including the persistent menu and the get started button, which must be set again.
 ///
 /// # Examples
 ///
@@ -40,7 +63,7 @@ use crate::Action;
 /// # Implements
 ///
 /// * `FromStr`
-/// * `ToString`
+/// * `Display`
 /// * `Default`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Payload {
@@ -87,19 +110,161 @@ impl Payload {
     pub fn get_data(&self) -> Data {
         self.data.clone().unwrap_or_default()
     }
+
+    /// Encodes the payload into the `ref` parameter of an m.me link.
+    ///
+    /// Facebook only allows alphanumeric characters, `-`, `_` and `=` in a `ref`, so the JSON payload is encoded in URL-safe base64.
+    pub fn to_ref(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.to_string())
+    }
+
+    /// Decodes the `ref` parameter of a referral.
+    ///
+    /// A `ref` built by `to_ref` gives back the payload. Any other `ref`, like `Promo` in `m.me/page?ref=Promo`, is taken as the path of the action, without data.
+    /// Such a plain `ref` is only routed to its action when the payloads are not signed, see the signature of `Payload`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// let payload = Payload::from_ref("Promo");
+    /// assert_eq!(payload.get_path(), "Promo");
+    /// ```
+    pub fn from_ref(referral: &str) -> Self {
+        decode_ref(referral)
+            .and_then(|json| Self::from_str(&json).ok())
+            .unwrap_or_else(|| Self {
+                path: referral.to_owned(),
+                data: None,
+            })
+    }
+
+    /// Builds the m.me link opening a conversation with the page, the referral is then routed to the action of the payload.
+    ///
+    /// # Parameters
+    ///
+    /// * `page: &str` - The username or the id of the page.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// let payload = Payload::new(Promo, Some(Data::new("SUMMER", None)));
+    /// let link = payload.m_me_link("my.page");
+    ///
+    /// #[action]
+    /// async fn Promo(res: Res, req: Req) {
+    ///     let code: String = req.data.get_value();
+    ///     res.send(TextModel::new(&req.user, &format!("Your code: {code}"))).await;
+    /// }
+    /// ```
+    pub fn m_me_link(&self, page: &str) -> String {
+        format!("https://m.me/{page}?ref={}", self.to_ref())
+    }
+
+    /// Returns `true` if the payloads are signed, when the `PAYLOAD_SECRET` env variable is set.
+    pub fn is_signed() -> bool {
+        secret().is_some()
+    }
+
+    /// Checks the signature of a serialized payload, any payload is authentic when no secret is set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// let payload = Payload::new(Refund, None).to_string();
+    /// assert!(Payload::is_authentic(&payload));
+    ///
+    /// #[action]
+    /// async fn Refund(res: Res, req: Req) {}
+    /// ```
+    pub fn is_authentic(payload: &str) -> bool {
+        verify_with(payload, secret().as_deref())
+    }
+
+    /// Serializes the payload with its signature keyed with `secret`, without signature when there is no secret.
+    fn sign_with(&self, secret: Option<&str>) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        let signature = secret.and_then(mac).map(|mut mac| {
+            mac.update(json.as_bytes());
+            hex::encode(&mac.finalize().into_bytes()[..SIGNATURE_LENGTH])
+        });
+        let signed = SignedPayload {
+            payload: self.clone(),
+            signature,
+        };
+        serde_json::to_string(&signed).unwrap_or_default()
+    }
+}
+
+/// Checks the signature of a serialized payload keyed with `secret`, any payload is authentic when there is no secret.
+fn verify_with(payload: &str, secret: Option<&str>) -> bool {
+    let Some(mut mac) = secret.and_then(mac) else {
+        return true;
+    };
+    let Ok(signed) = serde_json::from_str::<SignedPayload>(payload) else {
+        return false;
+    };
+    let signature = signed
+        .signature
+        .and_then(|signature| hex::decode(signature).ok())
+        .filter(|signature| signature.len() == SIGNATURE_LENGTH);
+    let Some(signature) = signature else {
+        return false;
+    };
+    mac.update(
+        serde_json::to_string(&signed.payload)
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    mac.verify_truncated_left(&signature).is_ok()
+}
+
+/// Decodes a `ref` built by `to_ref` into the serialized payload.
+pub(crate) fn decode_ref(referral: &str) -> Option<String> {
+    let json = URL_SAFE_NO_PAD.decode(referral).ok()?;
+    String::from_utf8(json).ok()
+}
+
+/// The number of bytes of the HMAC kept in the signature of the payloads.
+const SIGNATURE_LENGTH: usize = 16;
+
+/// Returns the `PAYLOAD_SECRET` env variable keying the signature of the payloads, if it is set.
+fn secret() -> Option<String> {
+    std::env::var("PAYLOAD_SECRET").ok()
+}
+
+fn mac(secret: &str) -> Option<HmacSha256> {
+    HmacSha256::new_from_slice(secret.as_bytes()).ok()
+}
+
+/// A `Payload` as it is serialized, with its signature.
+#[derive(Serialize, Deserialize)]
+struct SignedPayload {
+    #[serde(flatten)]
+    payload: Payload,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
 }
 
 impl FromStr for Payload {
     type Err = String;
 
     fn from_str(payload: &str) -> Result<Self, String> {
-        serde_json::from_str(payload).map_err(|err| err.to_string())
+        let signed = serde_json::from_str::<SignedPayload>(payload);
+        signed
+            .map(|signed| signed.payload)
+            .map_err(|err| err.to_string())
     }
 }
 
-impl ToString for Payload {
-    fn to_string(&self) -> String {
-        serde_json::to_string(self).unwrap_or_default()
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sign_with(secret().as_deref()))
     }
 }
 
@@ -111,3 +276,50 @@ impl Default for Payload {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_signature(payload: &Payload, signature: &str) -> String {
+        let signed = SignedPayload {
+            payload: payload.clone(),
+            signature: Some(signature.to_owned()),
+        };
+        serde_json::to_string(&signed).unwrap()
+    }
+
+    #[test]
+    fn signatures_are_checked_in_full() {
+        let payload = Payload::default();
+        let signed = payload.sign_with(Some("secret"));
+        assert!(verify_with(&signed, Some("secret")));
+        assert!(!verify_with(&signed, Some("other")));
+        assert!(!verify_with(
+            &signed.replace("Main", "Admin"),
+            Some("secret")
+        ));
+
+        let signature = serde_json::from_str::<SignedPayload>(&signed)
+            .unwrap()
+            .signature
+            .unwrap();
+        let short = with_signature(&payload, &signature[..2]);
+        let wrong = with_signature(&payload, &"0".repeat(signature.len()));
+        assert!(!verify_with(&short, Some("secret")));
+        assert!(!verify_with(&wrong, Some("secret")));
+        assert!(!verify_with(
+            r#"{"path":"Main","data":null}"#,
+            Some("secret")
+        ));
+        assert!(!verify_with("Promo", Some("secret")));
+    }
+
+    #[test]
+    fn nothing_is_signed_without_a_secret() {
+        let payload = Payload::default().sign_with(None);
+        assert_eq!(payload, r#"{"path":"Main","data":null}"#);
+        assert!(verify_with(&payload, None));
+        assert!(verify_with("Promo", None));
+    }
+}
@@ -1,5 +1,5 @@
 use super::button::Button;
-use super::ResponseModel;
+use super::{Platform, ResponseModel};
 use serde::Serialize;
 use serde_json::value::Value;
 
@@ -113,4 +113,13 @@ impl<'p> PersistentMenuModel<'p> {
 
 impl ResponseModel for PersistentMenuModel<'_> {
     const END_POINT: &'static str = "custom_user_settings";
+
+    fn check(&self, platform: Platform) -> Result<(), String> {
+        match platform {
+            Platform::Messenger => Ok(()),
+            Platform::Instagram => {
+                Err("user persistent menus are not supported on Instagram".to_owned())
+            }
+        }
+    }
 }
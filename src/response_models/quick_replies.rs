@@ -1,7 +1,11 @@
 use serde::Serialize;
 
-use super::ResponseModel;
-use super::{payload::Payload, recipient::Recipient};
+use super::payload::{Payload, MAX_PAYLOAD_LENGTH};
+use super::{recipient::Recipient, text::check_text};
+use super::{Platform, ResponseModel};
+use crate::core::history::Back;
+
+const MAX_QUICK_REPLIES: usize = 13;
 
 /// `QuickReply` is a struct that represents a quick reply button in a Messenger conversation.
 ///
@@ -95,6 +99,7 @@ struct QuickMessage {
 /// # Methods
 ///
 /// * `new(sender: &'q str, message: &str, quick_replies: Vec<QuickReply>) -> Self` - Creates a new `QuickReplyModel` instance.
+/// * `with_back(self, title: &str) -> Self` - Adds a quick reply going back to the previous action of the user, only the 12 first quick replies are kept before adding it.
 ///
 /// # Examples
 ///
@@ -156,8 +161,49 @@ impl<'q> QuickReplyModel<'q> {
             },
         }
     }
+
+    /// Adds a quick reply going back to the previous action of the user.
+    ///
+    /// Messenger shows at most 13 quick replies, so only the 12 first quick replies are kept before adding the `Back` one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use russenger::prelude::*;
+    ///
+    /// #[action]
+    /// async fn Colors(res: Res, req: Req) {
+    ///     let quick_replies = vec![QuickReply::new("Red", "", Payload::new(Colors, None))];
+    ///     let quick_reply_model = QuickReplyModel::new(&req.user, "Pick a color", quick_replies).with_back("Back");
+    ///     res.send(quick_reply_model).await;
+    /// }
+    /// ```
+    pub fn with_back(mut self, title: &str) -> Self {
+        let payload = Payload::new(Back, None);
+        let quick_replies = &mut self.message.quick_replies;
+        quick_replies.truncate(MAX_QUICK_REPLIES - 1);
+        quick_replies.push(QuickReply::new(title, "", payload));
+        self
+    }
 }
 
 impl ResponseModel for QuickReplyModel<'_> {
     const END_POINT: &'static str = "messages";
+
+    fn check(&self, platform: Platform) -> Result<(), String> {
+        if self.message.quick_replies.len() > MAX_QUICK_REPLIES {
+            return Err(format!("quick replies are limited to {MAX_QUICK_REPLIES}"));
+        }
+        let mut payloads = self
+            .message
+            .quick_replies
+            .iter()
+            .map(|quick| &quick.payload);
+        if payloads.any(|payload| payload.chars().count() > MAX_PAYLOAD_LENGTH) {
+            return Err(format!(
+                "payloads are limited to {MAX_PAYLOAD_LENGTH} characters, store the data with `Data::store`"
+            ));
+        }
+        check_text(&self.message.text, platform)
+    }
 }
@@ -1,7 +1,7 @@
 use serde::Serialize;
 
 use super::recipient::Recipient;
-use super::ResponseModel;
+use super::{Platform, ResponseModel};
 
 #[derive(Serialize)]
 struct Text<'t> {
@@ -48,7 +48,7 @@ pub struct TextModel<'s> {
 impl<'s> TextModel<'s> {
     /// Creates a new `TextModel`.
     ///
-    /// This method allows you to send a text message to the given recipient. Note that the number of characters to send is limited to 2000 characters.
+    /// This method allows you to send a text message to the given recipient. Note that the number of characters to send is limited to 2000 characters, 1000 on Instagram.
     ///
     /// # Arguments
     ///
@@ -78,4 +78,16 @@ impl<'s> TextModel<'s> {
 
 impl ResponseModel for TextModel<'_> {
     const END_POINT: &'static str = "messages";
+
+    fn check(&self, platform: Platform) -> Result<(), String> {
+        check_text(self.message.text, platform)
+    }
+}
+
+pub(crate) fn check_text(text: &str, platform: Platform) -> Result<(), String> {
+    let max = platform.max_text_length();
+    if text.chars().count() > max {
+        return Err(format!("the text exceeds {max} characters on {platform:?}"));
+    }
+    Ok(())
 }